@@ -0,0 +1,277 @@
+//! `#[derive(DojoModel)]`, which maps a Cairo model received from Torii to a Rust struct.
+//!
+//! ```ignore
+//! #[derive(DojoModel)]
+//! #[dojo(name = "di-Position")]
+//! pub struct Position {
+//!     #[dojo(as = "contract_address")]
+//!     pub player: Felt,
+//!     #[dojo(as = "coordinate")]
+//!     pub x: i32,
+//!     /// Missing on 2D worlds.
+//!     #[dojo(as = "coordinate", default)]
+//!     pub z: i32,
+//! }
+//! ```
+//!
+//! This generates a `TryFrom<&Struct>` implementation, using the helpers of
+//! `crate::demo::dojo::models`, and a `DojoModel` implementation whose `register` adds the
+//! model to the `ModelRegistry`:
+//!
+//! ```ignore
+//! Position::register(app);
+//! ```
+//!
+//! Struct attributes:
+//! - `name = "..."`: the full model name, required.
+//! - `event = MovedEvent`: emits every update as `MovedEvent(model)`.
+//! - `handler = handle_position`: hands every update to a `ModelHandler` function instead.
+//! - `component`: also stores the model as a component on the entity of its player.
+//! - `path = "my_crate::dojo"`: the module of the starter the code refers to.
+//!
+//! Without `event`, `handler` or `component`, the updates are only parsed, any error being
+//! reported like the ones of the other models.
+//!
+//! Field attributes:
+//! - `field = "..."`: the name of the Cairo member, the Rust field name by default.
+//! - `as = "..."`: one of `u8`, `u32`, `coordinate`, `felt`, `contract_address`, `bool`,
+//!   `felt_array` and `option_enum`. Inferred for `u8`, `u32`, `i32`, `bool`, `Felt` and
+//!   `Vec<Felt>` fields.
+//! - `default`: use `Default::default()` when the member is missing.
+//!
+//! The value read is converted with `Into`, so a `u8` member can fill a `u32` field.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, LitStr, Path, Type, parse_macro_input, parse_quote};
+
+#[proc_macro_derive(DojoModel, attributes(dojo))]
+pub fn derive_dojo_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let mut model_name: Option<LitStr> = None;
+    let mut path: Path = parse_quote!(crate::demo::dojo);
+    let mut event: Option<Path> = None;
+    let mut handler: Option<Path> = None;
+    let mut component = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("dojo"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                model_name = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("path") {
+                path = meta.value()?.parse::<LitStr>()?.parse()?;
+            } else if meta.path.is_ident("event") {
+                event = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("handler") {
+                handler = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("component") {
+                component = true;
+            } else {
+                return Err(meta.error(
+                    "unknown dojo attribute, expected `name`, `event`, `handler`, `component` \
+                     or `path`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    let model_name = model_name.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "missing `#[dojo(name = \"...\")]`, e.g. `di-Position`",
+        )
+    })?;
+
+    if let (Some(_), Some(handler)) = (&event, &handler) {
+        return Err(syn::Error::new_spanned(
+            handler,
+            "`event` and `handler` can't be combined, send the event from the handler",
+        ));
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "DojoModel can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &data.fields,
+            "DojoModel requires named fields",
+        ));
+    };
+
+    let initializers = fields
+        .named
+        .iter()
+        .map(|field| field_initializer(field, &path))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    // The crates are reached through the starter, the user of the derive may not depend on them.
+    let private = quote!(#path::registry::__private);
+    let registry = quote!(#path::registry::ModelRegistry);
+    let dispatch = match (event, handler) {
+        (Some(event), _) => Some(quote!(registry.register_model_event::<Self, _>(#event);)),
+        (None, Some(handler)) => Some(quote!(registry.register(Self::NAME, #handler);)),
+        (None, None) if component => None,
+        (None, None) => Some(quote! {
+            registry.register(Self::NAME, |_entity_id, model, _commands| {
+                <Self as ::core::convert::TryFrom<_>>::try_from(model).map(|_| ())
+            });
+        }),
+    };
+    let dispatch = dispatch.map(|dispatch| {
+        quote! {
+            let mut registry = app
+                .init_resource::<#registry>()
+                .world_mut()
+                .resource_mut::<#registry>();
+            #dispatch
+        }
+    });
+    let component = component.then(
+        || quote!(#path::registry::RegisterModelExt::register_model::<Self>(app, Self::NAME);),
+    );
+    Ok(quote! {
+        impl #impl_generics ::core::convert::TryFrom<&#private::dojo_types::schema::Struct>
+            for #ident #ty_generics #where_clause
+        {
+            type Error = #path::models::ModelParseError;
+
+            fn try_from(
+                struct_value: &#private::dojo_types::schema::Struct,
+            ) -> ::core::result::Result<Self, Self::Error> {
+                ::core::result::Result::Ok(Self {
+                    #(#initializers,)*
+                })
+            }
+        }
+
+        impl #impl_generics #path::registry::DojoModel for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #model_name;
+
+            fn register(app: &mut #private::App) {
+                #dispatch
+                #component
+            }
+        }
+    })
+}
+
+fn field_initializer(field: &syn::Field, path: &Path) -> syn::Result<TokenStream2> {
+    let Some(ident) = &field.ident else {
+        return Err(syn::Error::new_spanned(field, "expected a named field"));
+    };
+
+    let mut member = ident.to_string();
+    let mut kind: Option<LitStr> = None;
+    let mut default = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("dojo"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("field") {
+                member = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("as") {
+                kind = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("default") {
+                default = true;
+            } else {
+                return Err(
+                    meta.error("unknown dojo attribute, expected `field`, `as` or `default`")
+                );
+            }
+            Ok(())
+        })?;
+    }
+
+    let getter = match &kind {
+        Some(kind) => getter_for(&kind.value()).ok_or_else(|| {
+            syn::Error::new_spanned(
+                kind,
+                "unsupported kind, expected one of `u8`, `u32`, `coordinate`, `felt`, \
+                 `contract_address`, `bool`, `felt_array` or `option_enum`",
+            )
+        })?,
+        None => infer_getter(&field.ty).ok_or_else(|| {
+            syn::Error::new_spanned(
+                &field.ty,
+                "cannot infer how to read this field, add `#[dojo(as = \"...\")]`",
+            )
+        })?,
+    };
+    let getter = format_ident!("{}", getter);
+    let value = quote!(#path::models::#getter(struct_value, #member));
+
+    Ok(if default {
+        quote! {
+            #ident: match #value {
+                ::core::result::Result::Err(#path::models::ModelParseError::MissingField(_)) => {
+                    ::core::default::Default::default()
+                }
+                value => ::core::convert::Into::into(value?),
+            }
+        }
+    } else {
+        quote! {
+            #ident: ::core::convert::Into::into(#value?)
+        }
+    })
+}
+
+fn getter_for(kind: &str) -> Option<&'static str> {
+    Some(match kind {
+        "u8" => "get_u8",
+        "u32" => "get_u32",
+        "coordinate" => "get_coordinate",
+        "felt" => "get_felt",
+        "contract_address" => "get_contract_address",
+        "bool" => "get_bool",
+        "felt_array" => "get_felt_array",
+        "option_enum" => "get_option_enum_index",
+        _ => return None,
+    })
+}
+
+/// Picks the helper from the Rust type, for the unambiguous cases.
+fn infer_getter(ty: &Type) -> Option<&'static str> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    getter_for(match segment.ident.to_string().as_str() {
+        "u8" => "u8",
+        "u32" => "u32",
+        "i32" => "coordinate",
+        "bool" => "bool",
+        "Felt" => "felt",
+        "Vec" if is_vec_of_felt(&segment.arguments) => "felt_array",
+        _ => return None,
+    })
+}
+
+fn is_vec_of_felt(arguments: &syn::PathArguments) -> bool {
+    let syn::PathArguments::AngleBracketed(arguments) = arguments else {
+        return false;
+    };
+    matches!(
+        arguments.args.first(),
+        Some(syn::GenericArgument::Type(Type::Path(item)))
+            if item.path.segments.last().is_some_and(|segment| segment.ident == "Felt")
+    )
+}
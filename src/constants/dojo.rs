@@ -1,52 +1,689 @@
-use starknet::{core::types::Felt, macros::selector};
-use std::env;
+use serde::Deserialize;
+use starknet::{
+    core::{
+        types::Felt,
+        utils::{cairo_short_string_to_felt, get_selector_from_name},
+    },
+    macros::selector,
+};
+use std::{env, fmt, fs, io, path::Path, time::Duration};
+use url::{Host, Url};
 
 /// Configuration for Dojo blockchain integration
 #[derive(Debug, Clone)]
 pub struct DojoConfig {
     pub torii_url: String,
     pub katana_url: String,
+    /// Network the URLs point to, detected from `katana_url` when `None`, see
+    /// [`DojoConfig::network`].
+    pub network: Option<Network>,
     pub world_address: Felt,
+    /// Namespace the world's models are deployed in, replacing the [`DEFAULT_NAMESPACE`] of the
+    /// models declared by the game, see [`DojoConfig::model_name`].
+    pub namespace: String,
     pub action_address: Felt,
     pub use_dev_account: bool,
-    pub dev_account_index: u32,
+    /// Predeployed Katana account used when `use_dev_account` is set.
+    pub dev_account: DevAccount,
+    /// Account signing the transactions when `use_dev_account` is off.
+    pub account_address: Option<Felt>,
+    /// Private key of `account_address`, only read from `ACCOUNT_PRIVATE_KEY`.
+    ///
+    /// Keep it out of `dojo.toml` and version control, and only use a key made for this game:
+    /// it stays in memory for as long as the game runs.
+    pub account_private_key: Option<SecretFelt>,
+    /// Entrypoint called to spawn the player.
+    pub spawn_selector: Felt,
+    /// Entrypoint called to move the player.
+    pub move_selector: Felt,
+    /// Number of entities fetched per page during the initial sync.
+    pub query_limit: u32,
+    /// How long a Torii query, or a subscription, may take before it's reported as failed.
+    pub request_timeout: Duration,
+    /// Chain id Katana must report, e.g. `KATANA`, before any transaction is sent.
+    pub expected_chain_id: Option<Felt>,
+    /// Upper bound of the fee paid by a transaction, unbounded when `None`.
+    pub max_fee: Option<Felt>,
+    /// Safety margin applied to the estimated fee, e.g. 1.5 for 50% more.
+    pub fee_estimation_multiplier: f64,
 }
 
+/// The addresses of `manifest_dev.json` and a local Katana and Torii.
+///
+/// The environment is left out, see [`DojoConfig::from_env`].
 impl Default for DojoConfig {
     fn default() -> Self {
         Self {
-            torii_url: env::var("TORII_URL")
-                .unwrap_or_else(|_| "http://localhost:8080".to_string()),
-            katana_url: env::var("KATANA_URL")
-                .unwrap_or_else(|_| "http://0.0.0.0:5050".to_string()),
-            world_address: env::var("WORLD_ADDRESS")
-                .ok()
-                .and_then(|addr| Felt::from_hex(&addr).ok())
-                .unwrap_or_else(|| {
-                    // Real deployed world address from manifest_dev.json
-                    Felt::from_hex_unchecked(
-                        "0x058565b92f55fb07b53940b4b7eea3df2ac2878210e5c7a4c68201e8c511a546",
-                    )
-                }),
-            action_address: env::var("ACTION_ADDRESS")
-                .ok()
-                .and_then(|addr| Felt::from_hex(&addr).ok())
-                .unwrap_or_else(|| {
-                    // Real deployed action address from manifest_dev.json
-                    Felt::from_hex_unchecked(
-                        "0x049f9b281bb08aea6d745f28cf31dd529348b04a21d9a5ae1ef19197665c02da",
-                    )
-                }),
-            use_dev_account: env::var("USE_DEV_ACCOUNT").unwrap_or_else(|_| "true".to_string())
-                == "true",
-            dev_account_index: env::var("DEV_ACCOUNT_INDEX")
-                .unwrap_or_else(|_| "0".to_string())
-                .parse()
-                .unwrap_or(0),
-        }
-    }
-}
-
-// Contract functions
+            torii_url: "http://localhost:8080".to_string(),
+            katana_url: "http://0.0.0.0:5050".to_string(),
+            network: None,
+            // Real deployed world address from manifest_dev.json
+            world_address: Felt::from_hex_unchecked(
+                "0x058565b92f55fb07b53940b4b7eea3df2ac2878210e5c7a4c68201e8c511a546",
+            ),
+            namespace: DEFAULT_NAMESPACE.to_string(),
+            // Real deployed action address from manifest_dev.json
+            action_address: Felt::from_hex_unchecked(
+                "0x049f9b281bb08aea6d745f28cf31dd529348b04a21d9a5ae1ef19197665c02da",
+            ),
+            use_dev_account: true,
+            dev_account: DevAccount::Index(0),
+            account_address: None,
+            account_private_key: None,
+            spawn_selector: SPAWN_SELECTOR,
+            move_selector: MOVE_SELECTOR,
+            query_limit: DEFAULT_QUERY_LIMIT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            expected_chain_id: None,
+            max_fee: None,
+            fee_estimation_multiplier: DEFAULT_FEE_ESTIMATION_MULTIPLIER,
+        }
+    }
+}
+
+impl DojoConfig {
+    /// Loads the configuration from a `dojo.toml` file.
+    ///
+    /// Every key is optional and falls back to the same default as [`DojoConfig::default`].
+    /// Environment variables (`TORII_URL`, `WORLD_ADDRESS`, ...) override any value present
+    /// in the file, as in [`DojoConfig::from_env`].
+    ///
+    /// ```toml
+    /// torii_url = "http://localhost:8080"
+    /// katana_url = "http://0.0.0.0:5050"
+    /// # `local`, `sepolia`, `mainnet` or `remote`, detected from `katana_url` when missing.
+    /// network = "local"
+    /// world_address = "0x058565b92f55fb07b53940b4b7eea3df2ac2878210e5c7a4c68201e8c511a546"
+    /// namespace = "di"
+    /// action_address = "0x049f9b281bb08aea6d745f28cf31dd529348b04a21d9a5ae1ef19197665c02da"
+    /// use_dev_account = true
+    /// dev_account_index = 0
+    /// # Takes precedence over `dev_account_index`.
+    /// dev_account_address = "0x127fd5f1fe78a71f8bcd1fec63e3fe2f0486b6ecd5c86a0466c3a21fa5cfcec"
+    /// # The private key is only read from `ACCOUNT_PRIVATE_KEY`.
+    /// account_address = "0x127fd5f1fe78a71f8bcd1fec63e3fe2f0486b6ecd5c86a0466c3a21fa5cfcec"
+    /// # Either an entrypoint name or a raw hex selector.
+    /// spawn_selector = "spawn"
+    /// move_selector = "move"
+    /// query_limit = 100
+    /// request_timeout_secs = 15
+    /// # Either a short string or a raw hex felt.
+    /// expected_chain_id = "KATANA"
+    /// # In FRI, as a decimal or hex string.
+    /// max_fee = "1000000000000000000"
+    /// fee_estimation_multiplier = 1.5
+    /// ```
+    pub fn from_toml_path(path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let file: DojoConfigFile =
+            toml::from_str(&contents).map_err(|source| ConfigError::Toml {
+                path: path.display().to_string(),
+                source,
+            })?;
+
+        let defaults = Self::default();
+        let mut config = Self {
+            torii_url: file.torii_url.unwrap_or(defaults.torii_url),
+            katana_url: file.katana_url.unwrap_or(defaults.katana_url),
+            network: match file.network {
+                Some(value) => Some(parse_network("network", &value)?),
+                None => defaults.network,
+            },
+            world_address: match file.world_address {
+                Some(value) => parse_address("world_address", &value)?,
+                None => defaults.world_address,
+            },
+            namespace: file.namespace.unwrap_or(defaults.namespace),
+            action_address: match file.action_address {
+                Some(value) => parse_address("action_address", &value)?,
+                None => defaults.action_address,
+            },
+            use_dev_account: file.use_dev_account.unwrap_or(defaults.use_dev_account),
+            dev_account: match (file.dev_account_address, file.dev_account_index) {
+                (Some(value), _) => {
+                    DevAccount::Address(parse_address("dev_account_address", &value)?)
+                }
+                (None, Some(index)) => DevAccount::Index(index),
+                (None, None) => defaults.dev_account,
+            },
+            account_address: match file.account_address {
+                Some(value) => Some(parse_address("account_address", &value)?),
+                None => defaults.account_address,
+            },
+            account_private_key: defaults.account_private_key,
+            spawn_selector: match file.spawn_selector {
+                Some(value) => parse_selector("spawn_selector", &value)?,
+                None => defaults.spawn_selector,
+            },
+            move_selector: match file.move_selector {
+                Some(value) => parse_selector("move_selector", &value)?,
+                None => defaults.move_selector,
+            },
+            query_limit: file.query_limit.unwrap_or(defaults.query_limit),
+            request_timeout: file
+                .request_timeout_secs
+                .map_or(defaults.request_timeout, Duration::from_secs),
+            expected_chain_id: match file.expected_chain_id {
+                Some(value) => Some(parse_chain_id("expected_chain_id", &value)?),
+                None => defaults.expected_chain_id,
+            },
+            max_fee: match file.max_fee {
+                Some(value) => Some(parse_fee("max_fee", &value)?),
+                None => defaults.max_fee,
+            },
+            fee_estimation_multiplier: file
+                .fee_estimation_multiplier
+                .unwrap_or(defaults.fee_estimation_multiplier),
+        };
+        config.apply_env_overrides()?;
+
+        Ok(config)
+    }
+
+    /// The defaults, overridden by the environment variables (`TORII_URL`, `WORLD_ADDRESS`,
+    /// ...) that are set.
+    ///
+    /// A malformed value is reported as an error instead of falling back to the default.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        config.apply_env_overrides()?;
+        Ok(config)
+    }
+
+    /// The name a model declared by the game has in the world, e.g. `my_game-Position` for
+    /// `di-Position` in the `my_game` namespace. See [`namespaced_model_name`].
+    pub fn model_name(&self, declared: &str) -> String {
+        namespaced_model_name(&self.namespace, declared)
+    }
+
+    /// The network set in the configuration, or the one `katana_url` seems to point to.
+    pub fn network(&self) -> Network {
+        self.network
+            .unwrap_or_else(|| Network::detect(&self.katana_url))
+    }
+
+    /// Checks that the URLs and contract addresses are usable before connecting.
+    ///
+    /// All problems are collected so they can be reported at once.
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
+        for (key, value) in [
+            ("TORII_URL", &self.torii_url),
+            ("KATANA_URL", &self.katana_url),
+        ] {
+            if let Err(error) = validate_url(key, value) {
+                errors.push(error);
+            }
+        }
+
+        for (key, value) in [
+            ("WORLD_ADDRESS", self.world_address),
+            ("ACTION_ADDRESS", self.action_address),
+        ] {
+            if let Err(error) = validate_address(key, value) {
+                errors.push(error);
+            }
+        }
+
+        if !self.fee_estimation_multiplier.is_finite() || self.fee_estimation_multiplier < 1.0 {
+            errors.push(ConfigError::InvalidValue {
+                key: "FEE_ESTIMATION_MULTIPLIER",
+                value: self.fee_estimation_multiplier.to_string(),
+            });
+        }
+
+        // Torii joins the namespace and the model name with a `-`.
+        if self.namespace.is_empty() || self.namespace.contains('-') {
+            errors.push(ConfigError::InvalidValue {
+                key: "DOJO_NAMESPACE",
+                value: self.namespace.clone(),
+            });
+        }
+
+        if self.query_limit == 0 {
+            errors.push(ConfigError::InvalidValue {
+                key: "QUERY_LIMIT",
+                value: self.query_limit.to_string(),
+            });
+        }
+
+        if self.request_timeout.is_zero() {
+            errors.push(ConfigError::InvalidValue {
+                key: "REQUEST_TIMEOUT_SECS",
+                value: self.request_timeout.as_secs().to_string(),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Overrides the current values with the ones set in the environment.
+    fn apply_env_overrides(&mut self) -> Result<(), ConfigError> {
+        if let Ok(value) = env::var("TORII_URL") {
+            self.torii_url = value;
+        }
+        if let Ok(value) = env::var("KATANA_URL") {
+            self.katana_url = value;
+        }
+        if let Ok(value) = env::var("NETWORK") {
+            self.network = Some(parse_network("NETWORK", &value)?);
+        }
+        if let Ok(value) = env::var("WORLD_ADDRESS") {
+            self.world_address = parse_address("WORLD_ADDRESS", &value)?;
+        }
+        if let Ok(value) = env::var("DOJO_NAMESPACE") {
+            self.namespace = value;
+        }
+        if let Ok(value) = env::var("ACTION_ADDRESS") {
+            self.action_address = parse_address("ACTION_ADDRESS", &value)?;
+        }
+        if let Ok(value) = env::var("USE_DEV_ACCOUNT") {
+            self.use_dev_account = value == "true";
+        }
+        if let Ok(value) = env::var("DEV_ACCOUNT_INDEX") {
+            self.dev_account =
+                DevAccount::Index(value.parse().map_err(|_| ConfigError::InvalidValue {
+                    key: "DEV_ACCOUNT_INDEX",
+                    value,
+                })?);
+        }
+        if let Ok(value) = env::var("DEV_ACCOUNT_ADDRESS") {
+            self.dev_account = DevAccount::Address(parse_address("DEV_ACCOUNT_ADDRESS", &value)?);
+        }
+        if let Ok(value) = env::var("ACCOUNT_ADDRESS") {
+            self.account_address = Some(parse_address("ACCOUNT_ADDRESS", &value)?);
+        }
+        if let Ok(value) = env::var("ACCOUNT_PRIVATE_KEY") {
+            // The value itself is never put in the error, it would end up in the logs.
+            let key = Felt::from_hex(&value).map_err(|_| ConfigError::InvalidValue {
+                key: "ACCOUNT_PRIVATE_KEY",
+                value: "<redacted>".to_string(),
+            })?;
+            self.account_private_key = Some(SecretFelt::new(key));
+        }
+        if let Ok(value) = env::var("SPAWN_SELECTOR") {
+            self.spawn_selector = parse_selector("SPAWN_SELECTOR", &value)?;
+        }
+        if let Ok(value) = env::var("MOVE_SELECTOR") {
+            self.move_selector = parse_selector("MOVE_SELECTOR", &value)?;
+        }
+        if let Ok(value) = env::var("EXPECTED_CHAIN_ID") {
+            self.expected_chain_id = Some(parse_chain_id("EXPECTED_CHAIN_ID", &value)?);
+        }
+        if let Ok(value) = env::var("MAX_FEE") {
+            self.max_fee = Some(parse_fee("MAX_FEE", &value)?);
+        }
+        if let Ok(value) = env::var("FEE_ESTIMATION_MULTIPLIER") {
+            self.fee_estimation_multiplier =
+                value.parse().map_err(|_| ConfigError::InvalidValue {
+                    key: "FEE_ESTIMATION_MULTIPLIER",
+                    value,
+                })?;
+        }
+        if let Ok(value) = env::var("QUERY_LIMIT") {
+            self.query_limit = value.parse().map_err(|_| ConfigError::InvalidValue {
+                key: "QUERY_LIMIT",
+                value,
+            })?;
+        }
+        if let Ok(value) = env::var("REQUEST_TIMEOUT_SECS") {
+            let secs = value.parse().map_err(|_| ConfigError::InvalidValue {
+                key: "REQUEST_TIMEOUT_SECS",
+                value,
+            })?;
+            self.request_timeout = Duration::from_secs(secs);
+        }
+        Ok(())
+    }
+}
+
+/// Selects one of the accounts Katana predeploys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DevAccount {
+    /// Position in Katana's predeployed accounts.
+    Index(usize),
+    /// Address of a predeployed account, resolved to its index once Katana lists them.
+    Address(Felt),
+}
+
+impl DevAccount {
+    /// Finds the index of this account among the `predeployed` addresses.
+    pub fn resolve(self, predeployed: &[Felt]) -> Option<usize> {
+        match self {
+            Self::Index(index) => (index < predeployed.len()).then_some(index),
+            Self::Address(address) => predeployed.iter().position(|account| *account == address),
+        }
+    }
+}
+
+/// The network the game connects to.
+///
+/// Predeployed accounts only exist on a local Katana: on the other networks, the account has to
+/// be provided with `ACCOUNT_ADDRESS` and `ACCOUNT_PRIVATE_KEY`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    /// A Katana on this machine or the local network.
+    Local,
+    Sepolia,
+    Mainnet,
+    /// Any other remote network.
+    Remote,
+}
+
+impl Network {
+    /// Guesses the network from the RPC URL: local hosts are [`Network::Local`], the others are
+    /// recognized by name, or [`Network::Remote`].
+    pub fn detect(rpc_url: &str) -> Self {
+        let Ok(url) = Url::parse(rpc_url) else {
+            return Self::Local;
+        };
+        let is_local = match url.host() {
+            Some(Host::Domain(domain)) => domain == "localhost" || domain.ends_with(".localhost"),
+            Some(Host::Ipv4(ip)) => ip.is_loopback() || ip.is_unspecified() || ip.is_private(),
+            Some(Host::Ipv6(ip)) => ip.is_loopback() || ip.is_unspecified(),
+            None => true,
+        };
+        let url = url.as_str().to_lowercase();
+        if is_local {
+            Self::Local
+        } else if url.contains("sepolia") {
+            Self::Sepolia
+        } else if url.contains("mainnet") {
+            Self::Mainnet
+        } else {
+            Self::Remote
+        }
+    }
+
+    pub fn is_local(self) -> bool {
+        self == Self::Local
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Local => "local",
+            Self::Sepolia => "sepolia",
+            Self::Mainnet => "mainnet",
+            Self::Remote => "remote",
+        })
+    }
+}
+
+impl fmt::Display for DevAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "#{index}"),
+            Self::Address(address) => write!(f, "{address:#x}"),
+        }
+    }
+}
+
+/// A felt that must not be logged, such as a private key.
+///
+/// `Debug` prints `<redacted>`, so the config can still be logged as a whole.
+#[derive(Clone, Copy)]
+pub struct SecretFelt(Felt);
+
+impl SecretFelt {
+    pub fn new(value: Felt) -> Self {
+        Self(value)
+    }
+
+    /// The secret value, to be handed to the signer and nowhere else.
+    pub fn expose(&self) -> Felt {
+        self.0
+    }
+}
+
+impl fmt::Debug for SecretFelt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Raw contents of a `dojo.toml` file, before validation.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DojoConfigFile {
+    torii_url: Option<String>,
+    katana_url: Option<String>,
+    network: Option<String>,
+    world_address: Option<String>,
+    namespace: Option<String>,
+    action_address: Option<String>,
+    use_dev_account: Option<bool>,
+    dev_account_index: Option<usize>,
+    dev_account_address: Option<String>,
+    account_address: Option<String>,
+    spawn_selector: Option<String>,
+    move_selector: Option<String>,
+    query_limit: Option<u32>,
+    request_timeout_secs: Option<u64>,
+    expected_chain_id: Option<String>,
+    max_fee: Option<String>,
+    fee_estimation_multiplier: Option<f64>,
+}
+
+fn parse_address(key: &'static str, value: &str) -> Result<Felt, ConfigError> {
+    Felt::from_hex(value).map_err(|_| ConfigError::InvalidAddress {
+        key,
+        value: value.to_string(),
+    })
+}
+
+/// Accepts either a raw hex felt (`0x...`) or an entrypoint name, hashed the same way as `selector!`.
+fn parse_selector(key: &'static str, value: &str) -> Result<Felt, ConfigError> {
+    if value.starts_with("0x") {
+        return Felt::from_hex(value).map_err(|_| ConfigError::InvalidValue {
+            key,
+            value: value.to_string(),
+        });
+    }
+    get_selector_from_name(value).map_err(|_| ConfigError::InvalidValue {
+        key,
+        value: value.to_string(),
+    })
+}
+
+/// Accepts `local` (or `katana`), `sepolia`, `mainnet` or `remote`, in any case.
+fn parse_network(key: &'static str, value: &str) -> Result<Network, ConfigError> {
+    match value.to_lowercase().as_str() {
+        "local" | "katana" => Ok(Network::Local),
+        "sepolia" => Ok(Network::Sepolia),
+        "mainnet" => Ok(Network::Mainnet),
+        "remote" => Ok(Network::Remote),
+        _ => Err(ConfigError::InvalidValue {
+            key,
+            value: value.to_string(),
+        }),
+    }
+}
+
+/// Accepts either a raw hex felt (`0x...`) or a short string such as `KATANA` or `SN_SEPOLIA`.
+fn parse_chain_id(key: &'static str, value: &str) -> Result<Felt, ConfigError> {
+    let invalid = || ConfigError::InvalidValue {
+        key,
+        value: value.to_string(),
+    };
+    if value.starts_with("0x") {
+        return Felt::from_hex(value).map_err(|_| invalid());
+    }
+    cairo_short_string_to_felt(value).map_err(|_| invalid())
+}
+
+/// Accepts a fee as a decimal or hex (`0x...`) string.
+fn parse_fee(key: &'static str, value: &str) -> Result<Felt, ConfigError> {
+    let fee = if value.starts_with("0x") {
+        Felt::from_hex(value)
+    } else {
+        Felt::from_dec_str(value)
+    };
+    fee.map_err(|_| ConfigError::InvalidValue {
+        key,
+        value: value.to_string(),
+    })
+}
+
+fn validate_url(key: &'static str, value: &str) -> Result<(), ConfigError> {
+    let invalid = |reason: &str| ConfigError::InvalidUrl {
+        key,
+        value: value.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let url = Url::parse(value).map_err(|error| invalid(&error.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid("scheme must be http or https"));
+    }
+    if url.host().is_none() {
+        return Err(invalid("missing host"));
+    }
+    Ok(())
+}
+
+fn validate_address(key: &'static str, value: Felt) -> Result<(), ConfigError> {
+    // Contract addresses live in the range [1, 2^251).
+    if value == Felt::ZERO || value.to_bytes_be()[0] > 0x07 {
+        return Err(ConfigError::InvalidAddress {
+            key,
+            value: format!("{value:#x}"),
+        });
+    }
+    Ok(())
+}
+
+/// Errors that can occur while loading or validating a [`DojoConfig`].
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file could not be read.
+    Io { path: String, source: io::Error },
+    /// The config file is not valid TOML or contains unexpected keys.
+    Toml {
+        path: String,
+        source: toml::de::Error,
+    },
+    /// An address is not a valid hex felt, or not a usable contract address.
+    InvalidAddress { key: &'static str, value: String },
+    /// A URL is malformed or doesn't use `http`/`https`.
+    InvalidUrl {
+        key: &'static str,
+        value: String,
+        reason: String,
+    },
+    /// A value could not be parsed into the expected type.
+    InvalidValue { key: &'static str, value: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "failed to read {path}: {source}"),
+            Self::Toml { path, source } => write!(f, "failed to parse {path}: {source}"),
+            Self::InvalidAddress { key, value } => {
+                write!(f, "{key} is not a valid felt: {value:?}")
+            }
+            Self::InvalidUrl { key, value, reason } => {
+                write!(f, "{key} is not a valid URL ({reason}): {value:?}")
+            }
+            Self::InvalidValue { key, value } => write!(f, "{key} has an invalid value: {value:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Toml { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Default contract functions, used when no selector is configured.
 pub const SPAWN_SELECTOR: Felt = selector!("spawn");
 pub const MOVE_SELECTOR: Felt = selector!("move");
+
+/// Margin applied to estimated fees when `FEE_ESTIMATION_MULTIPLIER` isn't set.
+pub const DEFAULT_FEE_ESTIMATION_MULTIPLIER: f64 = 1.5;
+
+/// Namespace of the models declared by the game, used when `DOJO_NAMESPACE` isn't set.
+pub const DEFAULT_NAMESPACE: &str = "di";
+
+/// Moves a model name declared in the [`DEFAULT_NAMESPACE`] to `namespace`, e.g.
+/// `di-Position` to `my_game-Position`. Names in any other namespace are kept.
+pub fn namespaced_model_name(namespace: &str, declared: &str) -> String {
+    declared
+        .strip_prefix(DEFAULT_NAMESPACE)
+        .and_then(|name| name.strip_prefix('-'))
+        .map_or_else(
+            || declared.to_string(),
+            |name| format!("{namespace}-{name}"),
+        )
+}
+
+/// Entities fetched per page when `QUERY_LIMIT` isn't set.
+pub const DEFAULT_QUERY_LIMIT: u32 = 100;
+
+/// Timeout of the Torii requests when `REQUEST_TIMEOUT_SECS` isn't set.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_is_detected_from_the_rpc_url() {
+        for (url, network) in [
+            ("http://localhost:5050", Network::Local),
+            ("http://katana.localhost:5050", Network::Local),
+            ("http://0.0.0.0:5050", Network::Local),
+            ("http://127.0.0.1:5050", Network::Local),
+            ("http://192.168.1.20:5050", Network::Local),
+            ("http://[::1]:5050", Network::Local),
+            ("not a url", Network::Local),
+            (
+                "https://api.cartridge.gg/x/starknet/sepolia",
+                Network::Sepolia,
+            ),
+            (
+                "https://starknet-mainnet.public.blastapi.io",
+                Network::Mainnet,
+            ),
+            ("https://api.cartridge.gg/x/my-game/katana", Network::Remote),
+        ] {
+            assert_eq!(Network::detect(url), network, "{url}");
+        }
+    }
+
+    #[test]
+    fn network_names_are_parsed() {
+        for (value, network) in [
+            ("local", Network::Local),
+            ("katana", Network::Local),
+            ("Sepolia", Network::Sepolia),
+            ("MAINNET", Network::Mainnet),
+            ("remote", Network::Remote),
+        ] {
+            assert_eq!(
+                parse_network("NETWORK", value).ok(),
+                Some(network),
+                "{value}"
+            );
+        }
+        assert!(matches!(
+            parse_network("NETWORK", "goerli"),
+            Err(ConfigError::InvalidValue { key: "NETWORK", .. })
+        ));
+    }
+}
@@ -0,0 +1,510 @@
+//! Turn player actions into transactions.
+//!
+//! Input systems (keyboard, gamepad, ...) only write [`PlayerAction`] events, which are then
+//! rate-limited, optionally batched, and submitted here.
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::{DojoResource, TokioRuntime};
+use starknet::core::types::{Call, Felt};
+use std::{collections::VecDeque, time::Duration};
+
+use super::{
+    DojoSystemState, LOG_TARGET,
+    coordinates::{CoordinateScale, GridPosition},
+    intro::{PendingSpawn, Player, PositionUpdatedEvent, update_player_position},
+    metrics::DojoMetrics,
+    pause::DojoPaused,
+    tx::{TransactionTracker, TxId, TxStatus, TxStatusChanged},
+};
+use crate::AppSystems;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TxRateLimiter>();
+    app.init_resource::<MoveBatchConfig>();
+    app.init_resource::<MoveBatch>();
+    app.init_resource::<PlayerActionTxs>();
+    app.init_resource::<EarlyActionQueue>();
+    app.init_resource::<DirectionMap>();
+    app.init_resource::<AwaitedSpawn>();
+    app.add_event::<PlayerAction>();
+    app.add_event::<MoveSubmitted>();
+    app.add_event::<LocalSpawnConfirmed>();
+    app.add_systems(
+        Update,
+        (
+            submit_player_actions.run_if(resource_exists::<TokioRuntime>),
+            await_local_spawn.run_if(resource_changed::<PlayerActionTxs>),
+            confirm_local_spawn.after(update_player_position),
+        )
+            .chain()
+            .in_set(AppSystems::Update),
+    );
+}
+
+/// An action requested by the local player, whatever the input device.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerAction {
+    Spawn,
+    /// A direction: left, right, up, down, z-up and z-down from 0 to 5, sent to the `move`
+    /// entrypoint as mapped by the [`DirectionMap`], or dropped when it doesn't map it.
+    Move(u8),
+}
+
+/// Emitted for each move sent, or added to the current batch.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MoveSubmitted {
+    pub direction: u8,
+}
+
+/// Emitted once the spawn submitted by the local player took effect and its cube exists.
+///
+/// Fired for the first position Torii sends for the account after the spawn. When the player
+/// was already spawned in a previous session, the spawn may not move it, or may revert: the
+/// event then fires once the transaction is final, with the position of the existing cube.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LocalSpawnConfirmed {
+    pub player: Felt,
+    /// World position of the cube.
+    pub position: Vec3,
+}
+
+/// The spawn transaction waiting for the position of its sender.
+#[derive(Resource, Debug, Default)]
+struct AwaitedSpawn(Option<SpawnAttempt>);
+
+#[derive(Debug, Clone, Copy)]
+struct SpawnAttempt {
+    tx: TxId,
+    /// The account sending the transaction, which owns the spawned player.
+    player: Felt,
+}
+
+/// Drops input-driven transactions that arrive faster than `min_interval`.
+#[derive(Resource, Debug)]
+pub struct TxRateLimiter {
+    pub min_interval: Duration,
+    /// Elapsed app time when the last transaction was queued.
+    last_tx: Option<Duration>,
+}
+
+impl Default for TxRateLimiter {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_millis(100),
+            last_tx: None,
+        }
+    }
+}
+
+impl TxRateLimiter {
+    /// Whether enough time passed since the last transaction.
+    pub fn is_ready(&self, now: Duration) -> bool {
+        self.last_tx
+            .is_none_or(|last_tx| now.saturating_sub(last_tx) >= self.min_interval)
+    }
+
+    /// Returns `true` and records the transaction if enough time passed since the last one.
+    pub fn try_acquire(&mut self, now: Duration) -> bool {
+        if !self.is_ready(now) {
+            return false;
+        }
+        self.last_tx = Some(now);
+        true
+    }
+}
+
+/// The transactions submitted for the last actions, to look up in the [`TransactionTracker`].
+///
+/// ```ignore
+/// fn spawn_status(txs: Res<PlayerActionTxs>, tracker: Res<TransactionTracker>) {
+///     let status = txs.spawn.and_then(|id| tracker.status(id));
+/// }
+/// ```
+#[derive(Resource, Debug, Default)]
+pub struct PlayerActionTxs {
+    pub spawn: Option<TxId>,
+    /// A batch of moves shares a single transaction, known once the batch is submitted.
+    pub last_move: Option<TxId>,
+}
+
+/// Keeps the actions made before [`DojoSystemState::is_ready`], to submit them once it is.
+///
+/// They are submitted one at a time, as the [`TxRateLimiter`] lets them through, and the
+/// actions made meanwhile wait behind them. Disabled, the early actions are dropped.
+#[derive(Resource, Debug)]
+pub struct EarlyActionQueue {
+    pub enabled: bool,
+    /// Actions kept at most; the oldest are dropped beyond it.
+    pub max_len: usize,
+    actions: VecDeque<PlayerAction>,
+}
+
+impl Default for EarlyActionQueue {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_len: 8,
+            actions: VecDeque::new(),
+        }
+    }
+}
+
+impl EarlyActionQueue {
+    fn push(&mut self, action: PlayerAction) {
+        self.actions.push_back(action);
+        while self.actions.len() > self.max_len {
+            let dropped = self.actions.pop_front();
+            debug!(target: LOG_TARGET, ?dropped, "Early action dropped, the queue is full.");
+        }
+    }
+}
+
+/// Collects the moves made within `window` into a single multicall.
+///
+/// Batched moves bypass the [`TxRateLimiter`], since the window already throttles them.
+#[derive(Resource, Debug)]
+pub struct MoveBatchConfig {
+    pub enabled: bool,
+    pub window: Duration,
+}
+
+impl Default for MoveBatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window: Duration::from_millis(100),
+        }
+    }
+}
+
+/// The moves waiting for the batch window to elapse.
+#[derive(Resource, Debug, Default)]
+struct MoveBatch {
+    calls: Vec<Call>,
+    /// Elapsed app time of the first move in the batch.
+    opened_at: Option<Duration>,
+}
+
+impl MoveBatch {
+    /// Empties the batch, returning its calls unless there were none.
+    fn drain_calls(&mut self) -> Option<Vec<Call>> {
+        self.opened_at = None;
+        if self.calls.is_empty() {
+            return None;
+        }
+        debug!(target: LOG_TARGET, moves = self.calls.len(), "Submitting batched moves.");
+        Some(std::mem::take(&mut self.calls))
+    }
+}
+
+pub fn spawn_call(dojo_state: &DojoSystemState) -> Call {
+    Call {
+        to: dojo_state.config.action_address,
+        selector: dojo_state.config.spawn_selector,
+        calldata: vec![],
+    }
+}
+
+/// The value the `move` entrypoint expects for each direction of [`PlayerAction::Move`].
+///
+/// Defaults to the order of the `Direction` enum of the `actions` contract, which has no z
+/// directions. Contracts ordering their directions differently, or moving along z, only need
+/// to change this.
+#[derive(Resource, Debug, Clone)]
+pub struct DirectionMap(pub [Option<Felt>; 6]);
+
+impl Default for DirectionMap {
+    fn default() -> Self {
+        Self(std::array::from_fn(|direction| {
+            (direction < 4).then(|| Felt::from(direction))
+        }))
+    }
+}
+
+impl DirectionMap {
+    /// `None` for the directions the contract doesn't have, which can't be sent.
+    pub fn calldata(&self, direction: u8) -> Option<Felt> {
+        self.0.get(usize::from(direction)).copied().flatten()
+    }
+}
+
+/// The `move` call for `direction`, unless the [`DirectionMap`] doesn't map it.
+pub fn move_call(
+    dojo_state: &DojoSystemState,
+    directions: &DirectionMap,
+    direction: u8,
+) -> Option<Call> {
+    Some(Call {
+        to: dojo_state.config.action_address,
+        selector: dojo_state.config.move_selector,
+        calldata: vec![directions.calldata(direction)?],
+    })
+}
+
+fn submit_player_actions(
+    time: Res<Time>,
+    tokio: Res<TokioRuntime>,
+    dojo: Res<DojoResource>,
+    dojo_state: Res<DojoSystemState>,
+    directions: Res<DirectionMap>,
+    paused: Res<DojoPaused>,
+    batch_config: Res<MoveBatchConfig>,
+    mut batch: ResMut<MoveBatch>,
+    mut rate_limiter: ResMut<TxRateLimiter>,
+    mut tx_tracker: ResMut<TransactionTracker>,
+    mut action_txs: ResMut<PlayerActionTxs>,
+    mut early_actions: ResMut<EarlyActionQueue>,
+    mut metrics: ResMut<DojoMetrics>,
+    mut ev_player_action: EventReader<PlayerAction>,
+    mut ev_move_submitted: EventWriter<MoveSubmitted>,
+) {
+    // Transactions need a confirmed account on the expected chain, see `AccountConnectedEvent`.
+    // While the account reconnects, the tracker holds them until it's back. Dry runs don't send
+    // anything, so they go on without it.
+    if !dojo_state.is_ready() && !tx_tracker.is_holding() && !tx_tracker.is_dry_run() {
+        for action in ev_player_action.read() {
+            if early_actions.enabled {
+                early_actions.push(*action);
+            } else {
+                debug!(
+                    target: LOG_TARGET,
+                    ?action,
+                    "Action dropped, the account is not connected yet."
+                );
+            }
+        }
+        return;
+    }
+
+    if paused.0 {
+        for action in ev_player_action.read() {
+            debug!(target: LOG_TARGET, ?action, "Action dropped, Dojo is paused.");
+        }
+        return;
+    }
+
+    let now = time.elapsed();
+    let mut submit = |calls: Vec<Call>| {
+        metrics.txs_queued += 1;
+        tx_tracker.submit(&dojo, &tokio, calls, now)
+    };
+
+    let mut actions: Vec<PlayerAction> = ev_player_action.read().copied().collect();
+    if !early_actions.actions.is_empty() {
+        // The rate limiter would drop all but the first of them, while batched moves aren't
+        // limited.
+        for action in actions.drain(..) {
+            early_actions.push(action);
+        }
+        if batch_config.enabled {
+            actions.extend(early_actions.actions.drain(..));
+        } else if rate_limiter.is_ready(now) {
+            actions.extend(early_actions.actions.pop_front());
+        }
+        if !actions.is_empty() {
+            info!(
+                target: LOG_TARGET,
+                remaining = early_actions.actions.len(),
+                "Submitting the actions made while connecting"
+            );
+        }
+    }
+    for action in actions {
+        match action {
+            PlayerAction::Spawn => {
+                if let Some(calls) = batch.drain_calls() {
+                    action_txs.last_move = Some(submit(calls));
+                }
+                if !rate_limiter.try_acquire(now) {
+                    debug!(target: LOG_TARGET, "Spawn dropped by the transaction rate limiter.");
+                    continue;
+                }
+
+                info!(target: LOG_TARGET, "Spawning.");
+                action_txs.spawn = Some(submit(vec![spawn_call(&dojo_state)]));
+            }
+            PlayerAction::Move(direction) if directions.calldata(direction).is_none() => {
+                debug!(
+                    target: LOG_TARGET,
+                    direction,
+                    "Move dropped, the contract has no such direction."
+                );
+            }
+            PlayerAction::Move(direction) if batch_config.enabled => {
+                batch.opened_at.get_or_insert(now);
+                batch
+                    .calls
+                    .extend(move_call(&dojo_state, &directions, direction));
+                ev_move_submitted.write(MoveSubmitted { direction });
+            }
+            PlayerAction::Move(direction) => {
+                if !rate_limiter.try_acquire(now) {
+                    debug!(
+                        target: LOG_TARGET,
+                        direction,
+                        "Move dropped by the transaction rate limiter."
+                    );
+                    continue;
+                }
+
+                let calls = move_call(&dojo_state, &directions, direction)
+                    .into_iter()
+                    .collect();
+                action_txs.last_move = Some(submit(calls));
+                ev_move_submitted.write(MoveSubmitted { direction });
+            }
+        }
+    }
+
+    let window_elapsed = batch
+        .opened_at
+        .is_some_and(|opened_at| now.saturating_sub(opened_at) >= batch_config.window);
+    if batch_config.enabled && !window_elapsed {
+        return;
+    }
+    if let Some(calls) = batch.drain_calls() {
+        action_txs.last_move = Some(submit(calls));
+    }
+}
+
+/// Remembers the account behind the last spawn, its position being keyed by the address.
+fn await_local_spawn(
+    dojo_state: Res<DojoSystemState>,
+    action_txs: Res<PlayerActionTxs>,
+    mut awaited: ResMut<AwaitedSpawn>,
+) {
+    let Some(tx) = action_txs.spawn else {
+        return;
+    };
+    if awaited.0.is_some_and(|attempt| attempt.tx == tx) {
+        return;
+    }
+    let Some(player) = dojo_state.local_player_address else {
+        debug!(target: LOG_TARGET, "Spawn submitted without a known account address.");
+        return;
+    };
+    awaited.0 = Some(SpawnAttempt { tx, player });
+}
+
+fn confirm_local_spawn(
+    scale: Res<CoordinateScale>,
+    mut awaited: ResMut<AwaitedSpawn>,
+    mut ev_position_updated: EventReader<PositionUpdatedEvent>,
+    mut ev_tx_status: EventReader<TxStatusChanged>,
+    mut ev_spawn_confirmed: EventWriter<LocalSpawnConfirmed>,
+    players: Query<(&Player, &GridPosition), Without<PendingSpawn>>,
+) {
+    // Also read while not waiting, so the positions before the spawn can't confirm it.
+    let Some(attempt) = awaited.0 else {
+        ev_position_updated.clear();
+        ev_tx_status.clear();
+        return;
+    };
+
+    let position = ev_position_updated
+        .read()
+        .filter(|ev| ev.position.player == attempt.player)
+        .last()
+        .map(|ev| scale.to_world(ev.position.as_vec3()));
+    if let Some(position) = position {
+        info!(target: LOG_TARGET, player = ?attempt.player, "Spawn confirmed.");
+        ev_spawn_confirmed.write(LocalSpawnConfirmed {
+            player: attempt.player,
+            position,
+        });
+        awaited.0 = None;
+        return;
+    }
+
+    // No position came with the receipt, the player may have spawned in a previous session.
+    let Some(status) = ev_tx_status
+        .read()
+        .filter(|ev| ev.id == attempt.tx && ev.status.is_final())
+        .map(|ev| &ev.status)
+        .last()
+    else {
+        return;
+    };
+    let existing = players
+        .iter()
+        .find(|(player, _)| player.id == attempt.player);
+    match (existing, status) {
+        (Some((_, GridPosition(cell))), _) => {
+            info!(target: LOG_TARGET, player = ?attempt.player, "Player was already spawned.");
+            ev_spawn_confirmed.write(LocalSpawnConfirmed {
+                player: attempt.player,
+                position: scale.to_world(cell.as_vec3()),
+            });
+            awaited.0 = None;
+        }
+        // Torii usually lags behind the receipt, the position is still on its way.
+        (None, TxStatus::Confirmed) => {}
+        (None, status) => {
+            debug!(target: LOG_TARGET, ?status, "Spawn failed, not waiting for it anymore.");
+            awaited.0 = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::demo::dojo::{error::DojoErrorEvent, mock, tx};
+
+    const FRAME: Duration = Duration::from_millis(10);
+
+    fn app() -> (App, mock::MockDojo) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .init_resource::<DojoResource>()
+            .init_resource::<TokioRuntime>()
+            .insert_resource(DojoSystemState {
+                torii_connected: true,
+                account_connected: true,
+                ..default()
+            })
+            .init_resource::<DojoMetrics>()
+            .init_resource::<DojoPaused>()
+            .init_resource::<CoordinateScale>()
+            .add_event::<PositionUpdatedEvent>()
+            .add_event::<DojoErrorEvent>()
+            .add_plugins((tx::plugin, plugin));
+        let mock = mock::install_mock_dojo(&mut app);
+        (app, mock)
+    }
+
+    #[test]
+    fn moves_within_the_interval_queue_one_tx() {
+        let mut rate_limiter = TxRateLimiter::default();
+        assert!(rate_limiter.try_acquire(Duration::from_millis(1000)));
+        // Well within the default 100ms.
+        assert!(!rate_limiter.try_acquire(Duration::from_millis(1010)));
+        assert!(rate_limiter.try_acquire(Duration::from_millis(1100)));
+    }
+
+    #[test]
+    fn unmapped_directions_are_dropped() {
+        let dojo_state = DojoSystemState::default();
+        let directions = DirectionMap::default();
+        assert_eq!(directions.calldata(3), Some(Felt::from(3)));
+        // z-up, which the `Direction` enum of the contract doesn't have.
+        assert!(move_call(&dojo_state, &directions, 4).is_none());
+    }
+
+    #[test]
+    fn dry_runs_need_no_account() {
+        let (mut app, mock) = app();
+        app.insert_resource(DojoSystemState::default())
+            .insert_resource(tx::DryRun(true));
+        app.update();
+
+        app.world_mut().send_event(PlayerAction::Spawn);
+        app.update();
+
+        assert_eq!(app.world().resource::<TransactionTracker>().dry_runs(), 1);
+        assert!(mock.queued_calls().is_empty());
+    }
+}
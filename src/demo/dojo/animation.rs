@@ -0,0 +1,159 @@
+//! Small animations played when players appear in, or leave, the world.
+
+use bevy::prelude::*;
+
+use super::intro::{EntityTracker, Player, PositionUpdatedEvent};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SpawnAnimationSettings>();
+    app.init_resource::<DespawnAnimationSettings>();
+    app.add_systems(
+        Update,
+        (animate_spawns, (cancel_despawns, animate_despawns).chain()),
+    );
+}
+
+/// How long newly appearing players take to grow to full size. Zero disables the animation.
+#[derive(Resource, Debug)]
+pub struct SpawnAnimationSettings {
+    pub duration: f32,
+}
+
+impl Default for SpawnAnimationSettings {
+    fn default() -> Self {
+        Self { duration: 0.3 }
+    }
+}
+
+/// Scales a freshly spawned player from zero to full size, removed once complete.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SpawnAnimation {
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl SpawnAnimation {
+    /// Returns `None` when the animation is disabled.
+    pub fn from_settings(settings: &SpawnAnimationSettings) -> Option<Self> {
+        (settings.duration > 0.0).then_some(Self {
+            elapsed: 0.0,
+            duration: settings.duration,
+        })
+    }
+
+    /// The scale reached so far, with a cubic ease-out.
+    pub fn scale(&self) -> f32 {
+        let t = (self.elapsed / self.duration).clamp(0.0, 1.0);
+        1.0 - (1.0 - t).powi(3)
+    }
+}
+
+fn animate_spawns(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut SpawnAnimation)>,
+) {
+    for (entity, mut transform, mut animation) in &mut query {
+        animation.elapsed += time.delta_secs();
+        transform.scale = Vec3::splat(animation.scale());
+
+        if animation.elapsed >= animation.duration {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<SpawnAnimation>();
+        }
+    }
+}
+
+/// How long leaving players take to shrink and fade out. Zero despawns them right away.
+#[derive(Resource, Debug)]
+pub struct DespawnAnimationSettings {
+    pub duration: f32,
+}
+
+impl Default for DespawnAnimationSettings {
+    fn default() -> Self {
+        Self { duration: 0.3 }
+    }
+}
+
+/// Shrinks and fades out a leaving player, which is despawned once complete.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct DespawnAnimation {
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl DespawnAnimation {
+    /// Returns `None` when the animation is disabled.
+    pub fn from_settings(settings: &DespawnAnimationSettings) -> Option<Self> {
+        (settings.duration > 0.0).then_some(Self {
+            elapsed: 0.0,
+            duration: settings.duration,
+        })
+    }
+
+    /// What remains of the cube, from 1 down to 0, with a cubic ease-in.
+    pub fn remaining(&self) -> f32 {
+        let t = (self.elapsed / self.duration).clamp(0.0, 1.0);
+        1.0 - t.powi(3)
+    }
+}
+
+fn animate_despawns(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entity_tracker: ResMut<EntityTracker>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut query: Query<(
+        Entity,
+        &Player,
+        &mut Transform,
+        &MeshMaterial3d<StandardMaterial>,
+        &mut DespawnAnimation,
+    )>,
+) {
+    for (entity, player, mut transform, material, mut animation) in &mut query {
+        animation.elapsed += time.delta_secs();
+        let remaining = animation.remaining();
+        transform.scale = Vec3::splat(remaining);
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.alpha_mode = AlphaMode::Blend;
+            material.base_color.set_alpha(remaining);
+        }
+
+        if animation.elapsed >= animation.duration {
+            commands.entity(entity).despawn();
+            entity_tracker.forget(player.id);
+        }
+    }
+}
+
+/// Brings back the players that rejoined while fading out.
+fn cancel_despawns(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ev_position_updated: EventReader<PositionUpdatedEvent>,
+    mut query: Query<
+        (
+            Entity,
+            &Player,
+            &mut Transform,
+            &MeshMaterial3d<StandardMaterial>,
+        ),
+        With<DespawnAnimation>,
+    >,
+) {
+    for ev in ev_position_updated.read() {
+        for (entity, player, mut transform, material) in &mut query {
+            if player.id != ev.position.player {
+                continue;
+            }
+            transform.scale = Vec3::ONE;
+            if let Some(material) = materials.get_mut(&material.0) {
+                material.alpha_mode = AlphaMode::Opaque;
+                material.base_color.set_alpha(1.0);
+            }
+            commands.entity(entity).remove::<DespawnAnimation>();
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! How player entities look, so another game can swap the cube without touching the spawn logic.
+
+use bevy::prelude::*;
+use starknet::core::types::Felt;
+
+use super::{
+    animation::DespawnAnimation,
+    intro::{LocalPlayer, Player, color_for_player},
+    persistence::Restored,
+    stale::Stale,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PlayerAppearance>();
+    app.init_resource::<LocalPlayerHighlight>();
+    app.init_resource::<ColorblindSafe>();
+    app.add_systems(
+        Update,
+        (
+            apply_colorblind_safe.run_if(resource_changed::<ColorblindSafe>),
+            highlight_local_player,
+        ),
+    );
+}
+
+/// Derives the player colors from [`OKABE_ITO_PALETTE`] rather than from the whole hue range,
+/// for colorblind users. Toggling it recolors the players already spawned.
+///
+/// [`OKABE_ITO_PALETTE`]: super::intro::OKABE_ITO_PALETTE
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct ColorblindSafe(pub bool);
+
+/// Shape of the player entities.
+#[derive(Debug, Clone)]
+pub enum PlayerMeshKind {
+    Cube,
+    Sphere,
+    Capsule,
+    /// A mesh loaded or built by the game, `size` is then ignored.
+    Custom(Handle<Mesh>),
+}
+
+/// Consulted whenever a player entity is given its mesh and material.
+#[derive(Resource, Debug, Clone)]
+pub struct PlayerAppearance {
+    pub mesh: PlayerMeshKind,
+    /// Size of the shape along each axis, in world units.
+    pub size: f32,
+    /// Color of every player, or `None` for a color derived from each address.
+    pub color: Option<Color>,
+    /// Kept in sync with the [`ColorblindSafe`] resource.
+    colorblind_safe: bool,
+    pub perceptual_roughness: f32,
+    pub metallic: f32,
+}
+
+impl Default for PlayerAppearance {
+    fn default() -> Self {
+        Self {
+            mesh: PlayerMeshKind::Cube,
+            size: 0.5,
+            color: None,
+            colorblind_safe: false,
+            perceptual_roughness: StandardMaterial::default().perceptual_roughness,
+            metallic: StandardMaterial::default().metallic,
+        }
+    }
+}
+
+impl PlayerAppearance {
+    pub fn mesh(&self, meshes: &mut Assets<Mesh>) -> Handle<Mesh> {
+        let size = self.size;
+        match &self.mesh {
+            PlayerMeshKind::Cube => meshes.add(Cuboid::new(size, size, size)),
+            PlayerMeshKind::Sphere => meshes.add(Sphere::new(size / 2.0)),
+            PlayerMeshKind::Capsule => meshes.add(Capsule3d::new(size / 4.0, size / 2.0)),
+            PlayerMeshKind::Custom(handle) => handle.clone(),
+        }
+    }
+
+    /// The color derived from the address of `player`, ignoring [`PlayerAppearance::color`].
+    pub fn player_color(&self, player: Felt) -> Color {
+        color_for_player(player, self.colorblind_safe)
+    }
+
+    pub fn material(&self, player: Felt) -> StandardMaterial {
+        StandardMaterial {
+            base_color: self.color.unwrap_or_else(|| self.player_color(player)),
+            perceptual_roughness: self.perceptual_roughness,
+            metallic: self.metallic,
+            ..default()
+        }
+    }
+}
+
+/// Makes the cube of the [`LocalPlayer`] glow, on top of its base color.
+///
+/// Each player has its own material, so the other players keep theirs untouched.
+#[derive(Resource, Debug, Clone)]
+pub struct LocalPlayerHighlight {
+    pub enabled: bool,
+    pub emissive: Color,
+}
+
+impl Default for LocalPlayerHighlight {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            emissive: Color::srgb(0.6, 0.6, 0.6),
+        }
+    }
+}
+
+/// The stale and restored players are dimmed, they get their new color once they're back. The
+/// despawning ones are fading out and keep theirs.
+fn apply_colorblind_safe(
+    colorblind_safe: Res<ColorblindSafe>,
+    mut appearance: ResMut<PlayerAppearance>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    players: Query<
+        (&Player, &MeshMaterial3d<StandardMaterial>),
+        (Without<Stale>, Without<Restored>, Without<DespawnAnimation>),
+    >,
+) {
+    appearance.colorblind_safe = colorblind_safe.0;
+    for (player, material) in &players {
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = appearance.material(player.id).base_color;
+        }
+    }
+}
+
+/// Applied when the local player is tagged, and again whenever the highlight changes.
+fn highlight_local_player(
+    highlight: Res<LocalPlayerHighlight>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    local_players: Query<(&MeshMaterial3d<StandardMaterial>, Ref<LocalPlayer>)>,
+) {
+    let emissive = if highlight.enabled {
+        highlight.emissive.to_linear()
+    } else {
+        LinearRgba::BLACK
+    };
+    for (material, local_player) in &local_players {
+        if !highlight.is_changed() && !local_player.is_added() {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.emissive = emissive;
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Sound effects for the Dojo events, from the handles set in [`AudioConfig`].
+
+use bevy::prelude::*;
+
+use super::{
+    actions::LocalSpawnConfirmed,
+    error::DojoErrorEvent,
+    intro::{LocalPlayer, Player, PlayerMovedEvent},
+};
+use crate::audio::sound_effect;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AudioConfig>();
+    app.add_systems(
+        Update,
+        (
+            play_spawn_sound.run_if(|config: Res<AudioConfig>| config.spawn.is_some()),
+            play_move_sound.run_if(|config: Res<AudioConfig>| config.movement.is_some()),
+            play_error_sound.run_if(|config: Res<AudioConfig>| config.error.is_some()),
+        ),
+    );
+}
+
+/// The sounds played for the Dojo events. No asset is bundled, an event without a handle is
+/// silent, and its system doesn't run.
+///
+/// ```ignore
+/// fn load_dojo_sounds(asset_server: Res<AssetServer>, mut audio: ResMut<AudioConfig>) {
+///     audio.spawn = Some(asset_server.load("audio/sound_effects/spawn.ogg"));
+/// }
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct AudioConfig {
+    /// Played on [`LocalSpawnConfirmed`].
+    pub spawn: Option<Handle<AudioSource>>,
+    /// Played on [`PlayerMovedEvent`].
+    pub movement: Option<Handle<AudioSource>>,
+    /// Only plays the movement sound for the moves of the [`LocalPlayer`].
+    pub local_moves_only: bool,
+    /// Played on [`DojoErrorEvent`].
+    pub error: Option<Handle<AudioSource>>,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            spawn: None,
+            movement: None,
+            local_moves_only: true,
+            error: None,
+        }
+    }
+}
+
+fn play_spawn_sound(
+    mut commands: Commands,
+    config: Res<AudioConfig>,
+    mut ev_spawn_confirmed: EventReader<LocalSpawnConfirmed>,
+) {
+    if ev_spawn_confirmed.read().count() == 0 {
+        return;
+    }
+    if let Some(handle) = &config.spawn {
+        commands.spawn(sound_effect(handle.clone()));
+    }
+}
+
+/// Plays the sound once per frame at most, however many players moved.
+fn play_move_sound(
+    mut commands: Commands,
+    config: Res<AudioConfig>,
+    mut ev_player_moved: EventReader<PlayerMovedEvent>,
+    local_player: Query<&Player, With<LocalPlayer>>,
+) {
+    let local = local_player.single().ok().map(|player| player.id);
+    let moved = ev_player_moved
+        .read()
+        .filter(|ev| !config.local_moves_only || Some(ev.player) == local)
+        .count();
+    if moved == 0 {
+        return;
+    }
+    if let Some(handle) = &config.movement {
+        commands.spawn(sound_effect(handle.clone()));
+    }
+}
+
+fn play_error_sound(
+    mut commands: Commands,
+    config: Res<AudioConfig>,
+    mut ev_error: EventReader<DojoErrorEvent>,
+) {
+    if ev_error.read().count() == 0 {
+        return;
+    }
+    if let Some(handle) = &config.error {
+        commands.spawn(sound_effect(handle.clone()));
+    }
+}
@@ -0,0 +1,54 @@
+//! Keep the locally controlled player in view.
+
+use bevy::prelude::*;
+
+use super::intro::LocalPlayer;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CameraFollow>();
+    app.add_systems(
+        PostUpdate,
+        camera_follow_local_player.before(TransformSystem::TransformPropagate),
+    );
+}
+
+/// How the camera follows the local player.
+#[derive(Resource, Debug)]
+pub struct CameraFollow {
+    /// Time in seconds for the camera to close most of the distance. Zero follows instantly.
+    pub lag: f32,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self { lag: 0.15 }
+    }
+}
+
+/// Keeps the local player centered. The camera stays where it is until a local player is known.
+fn camera_follow_local_player(
+    time: Res<Time>,
+    follow: Res<CameraFollow>,
+    local_player: Query<&Transform, (With<LocalPlayer>, Without<Camera>)>,
+    // The 2D camera of the menus stays put.
+    mut cameras: Query<&mut Transform, (With<Camera>, Without<Camera2d>)>,
+) {
+    let Ok(player_transform) = local_player.single() else {
+        return;
+    };
+
+    let t = if follow.lag <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-time.delta_secs() / follow.lag).exp()
+    };
+
+    for mut camera_transform in &mut cameras {
+        // Only follow on the XY plane, the camera keeps its distance to the scene.
+        let target = player_transform
+            .translation
+            .xy()
+            .extend(camera_transform.translation.z);
+        camera_transform.translation = camera_transform.translation.lerp(target, t);
+    }
+}
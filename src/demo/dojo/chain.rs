@@ -0,0 +1,105 @@
+//! Makes sure Katana serves the expected network before sending transactions.
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::TokioRuntime;
+use starknet::{
+    core::{types::Felt, utils::parse_cairo_short_string},
+    providers::{JsonRpcClient, Provider, jsonrpc::HttpTransport},
+};
+use std::sync::{
+    Mutex,
+    mpsc::{Receiver, channel},
+};
+use url::Url;
+
+use super::{
+    DojoSystemState, LOG_TARGET,
+    error::{DojoError, DojoErrorEvent},
+    task,
+};
+use crate::constants::dojo::DojoConfig;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        receive_chain_id.run_if(resource_exists::<ChainIdCheck>),
+    );
+}
+
+/// A chain id request in flight, compared to `expected` once answered.
+#[derive(Resource)]
+pub struct ChainIdCheck {
+    expected: Felt,
+    receiver: Mutex<Receiver<Result<Felt, String>>>,
+}
+
+/// Queries the chain id of the RPC, if the config expects one.
+pub fn start_chain_id_check(commands: &mut Commands, tokio: &TokioRuntime, config: &DojoConfig) {
+    let Some(expected) = config.expected_chain_id else {
+        return;
+    };
+    let Ok(url) = Url::parse(&config.katana_url) else {
+        return;
+    };
+
+    let (sender, receiver) = channel();
+    task::spawn(tokio, async move {
+        let provider = JsonRpcClient::new(HttpTransport::new(url));
+        let result = provider.chain_id().await.map_err(|error| error.to_string());
+        let _ = sender.send(result);
+    });
+
+    commands.insert_resource(ChainIdCheck {
+        expected,
+        receiver: Mutex::new(receiver),
+    });
+}
+
+/// Formats a chain id as its short string when possible, e.g. `KATANA`.
+pub fn display_chain_id(chain_id: Felt) -> String {
+    parse_cairo_short_string(&chain_id).unwrap_or_else(|_| format!("{chain_id:#x}"))
+}
+
+fn receive_chain_id(
+    mut commands: Commands,
+    check: Res<ChainIdCheck>,
+    mut dojo_state: ResMut<DojoSystemState>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    let Some(result) = check
+        .receiver
+        .lock()
+        .ok()
+        .and_then(|receiver| receiver.try_recv().ok())
+    else {
+        return;
+    };
+    commands.remove_resource::<ChainIdCheck>();
+
+    match result {
+        Ok(chain_id) if chain_id == check.expected => {
+            info!(target: LOG_TARGET, "Connected to chain {}", display_chain_id(chain_id));
+        }
+        Ok(chain_id) => {
+            dojo_state.wrong_chain = true;
+            dojo_state.account_connected = false;
+            ev_error.write(
+                DojoError::ConnectionFailed(format!(
+                    "wrong network: expected chain {}, Katana serves {}",
+                    display_chain_id(check.expected),
+                    display_chain_id(chain_id)
+                ))
+                .into(),
+            );
+        }
+        Err(error) => {
+            // The check is a guardrail: without an answer, transactions stay blocked.
+            dojo_state.wrong_chain = true;
+            dojo_state.account_connected = false;
+            ev_error.write(
+                DojoError::ConnectionFailed(format!("could not verify the chain id: {error}"))
+                    .into(),
+            );
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Maps the onchain grid coordinates to world space.
+
+use bevy::prelude::*;
+
+use super::{
+    intro::{PlayerInterpolation, update_player_position},
+    prediction::Prediction,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CoordinateScale>();
+    app.init_resource::<WorldBounds>();
+    app.add_systems(
+        Update,
+        rescale_players
+            .after(update_player_position)
+            .run_if(resource_changed::<CoordinateScale>),
+    );
+}
+
+/// How a grid cell translates to world units, for every player, trail and grid line.
+///
+/// Changing it moves the existing players to their rescaled position.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct CoordinateScale {
+    pub units_per_cell: f32,
+    /// World position of the cell `(0, 0)`, to center the grid.
+    pub origin_offset: Vec2,
+}
+
+impl Default for CoordinateScale {
+    fn default() -> Self {
+        Self {
+            units_per_cell: 1.0,
+            origin_offset: Vec2::ZERO,
+        }
+    }
+}
+
+impl CoordinateScale {
+    /// Converts a position in cells, possibly fractional, to world space.
+    pub fn to_world(self, cell: Vec3) -> Vec3 {
+        cell * self.units_per_cell + self.origin_offset.extend(0.0)
+    }
+}
+
+/// The cells a player can be rendered at, guarding the camera against a malformed or
+/// malicious position, e.g. a `u32` coordinate millions of cells away.
+///
+/// Only the `x` and `y` coordinates are bounded. The [`super::intro::PositionUpdatedEvent`]s
+/// keep the position sent by Torii.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct WorldBounds {
+    pub min: IVec2,
+    pub max: IVec2,
+    pub out_of_bounds: OutOfBounds,
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self {
+            min: IVec2::splat(-10_000),
+            max: IVec2::splat(10_000),
+            out_of_bounds: OutOfBounds::default(),
+        }
+    }
+}
+
+impl WorldBounds {
+    pub fn contains(&self, cell: IVec3) -> bool {
+        let cell = cell.truncate();
+        cell.cmpge(self.min).all() && cell.cmple(self.max).all()
+    }
+
+    /// The closest cell within the bounds.
+    pub fn clamp(&self, cell: IVec3) -> IVec3 {
+        cell.truncate()
+            .clamp(self.min.min(self.max), self.max.max(self.min))
+            .extend(cell.z)
+    }
+}
+
+/// What happens to a player whose position is outside the [`WorldBounds`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutOfBounds {
+    /// Rendered at the closest cell within the bounds.
+    #[default]
+    Clamp,
+    /// Hidden at the closest cell within the bounds, until it moves back in.
+    Hide,
+}
+
+/// The last onchain position of a player, in cells.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridPosition(pub IVec3);
+
+fn rescale_players(
+    scale: Res<CoordinateScale>,
+    mut players: Query<(
+        &GridPosition,
+        &mut Transform,
+        &mut PlayerInterpolation,
+        Option<&mut Prediction>,
+    )>,
+) {
+    for (grid_position, mut transform, mut interpolation, prediction) in &mut players {
+        let position = scale.to_world(grid_position.0.as_vec3());
+        transform.translation = position;
+        interpolation.target = position;
+        if let Some(mut prediction) = prediction {
+            prediction.snap_to(position);
+        }
+    }
+}
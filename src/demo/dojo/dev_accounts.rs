@@ -0,0 +1,278 @@
+//! Lists the accounts Katana predeploys, so one can be picked by address.
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::{DojoResource, TokioRuntime};
+use serde::Deserialize;
+use starknet::core::types::Felt;
+use std::sync::{
+    Mutex,
+    mpsc::{Receiver, channel},
+};
+
+use super::{
+    LOG_TARGET, PendingAccountConnection,
+    error::{DojoError, DojoErrorEvent},
+    task,
+};
+use crate::constants::dojo::{DevAccount, DojoConfig};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        receive_dev_accounts.run_if(resource_exists::<DevAccountLookup>),
+    );
+}
+
+/// Addresses of the accounts predeployed by Katana, in index order.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DevAccounts(pub Vec<Felt>);
+
+/// A `dev_predeployedAccounts` request in flight.
+///
+/// `connect` is set when the account can only be connected once its index is known.
+#[derive(Resource)]
+pub struct DevAccountLookup {
+    requested: DevAccount,
+    katana_url: String,
+    connect: bool,
+    receiver: Mutex<Receiver<Result<Vec<Felt>, String>>>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Vec<PredeployedAccount>>,
+    error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    fn into_accounts(self) -> Result<Vec<Felt>, String> {
+        match (self.result, self.error) {
+            (Some(accounts), _) => Ok(accounts
+                .into_iter()
+                .map(|account| account.address)
+                .collect()),
+            (None, Some(error)) => Err(error.message),
+            (None, None) => Err("empty response".to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct PredeployedAccount {
+    address: Felt,
+}
+
+/// Fetches the predeployed accounts from Katana.
+///
+/// With [`DevAccount::Address`], the account is connected once its index is resolved.
+pub fn start_dev_account_lookup(
+    commands: &mut Commands,
+    tokio: &TokioRuntime,
+    config: &DojoConfig,
+) {
+    let (sender, receiver) = channel();
+    let url = config.katana_url.clone();
+    task::spawn(tokio, async move {
+        let _ = sender.send(fetch_predeployed_accounts(&url).await);
+    });
+
+    commands.insert_resource(DevAccountLookup {
+        requested: config.dev_account,
+        katana_url: config.katana_url.clone(),
+        connect: matches!(config.dev_account, DevAccount::Address(_)),
+        receiver: Mutex::new(receiver),
+    });
+}
+
+async fn fetch_predeployed_accounts(url: &str) -> Result<Vec<Felt>, String> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "dev_predeployedAccounts",
+        "params": [],
+    });
+    let response: RpcResponse = reqwest::Client::new()
+        .post(url)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|error| error.to_string())?
+        .json()
+        .await
+        .map_err(|error| error.to_string())?;
+    response.into_accounts()
+}
+
+fn receive_dev_accounts(
+    mut commands: Commands,
+    lookup: Res<DevAccountLookup>,
+    tokio: Res<TokioRuntime>,
+    mut dojo: ResMut<DojoResource>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    let Some(result) = lookup
+        .receiver
+        .lock()
+        .ok()
+        .and_then(|receiver| receiver.try_recv().ok())
+    else {
+        return;
+    };
+    commands.remove_resource::<DevAccountLookup>();
+
+    let accounts = match result {
+        Ok(accounts) => accounts,
+        Err(error) => {
+            // Katana may not expose the dev API, which only matters to resolve an address.
+            if lookup.connect {
+                commands.remove_resource::<PendingAccountConnection>();
+                ev_error.write(
+                    DojoError::ConnectionFailed(format!(
+                        "could not list the predeployed accounts: {error}"
+                    ))
+                    .into(),
+                );
+            } else {
+                debug!(target: LOG_TARGET, %error, "Predeployed accounts unavailable");
+            }
+            return;
+        }
+    };
+    debug!(target: LOG_TARGET, accounts = accounts.len(), "Predeployed accounts listed");
+
+    match lookup.requested.resolve(&accounts) {
+        Some(index) if lookup.connect => {
+            info!(target: LOG_TARGET, account = %lookup.requested, index, "Dev account resolved");
+            dojo.connect_predeployed_account(&tokio, lookup.katana_url.clone(), index);
+        }
+        Some(_) => {}
+        None => {
+            commands.remove_resource::<PendingAccountConnection>();
+            ev_error.write(
+                DojoError::ConnectionFailed(format!(
+                    "dev account {} is not one of the {} accounts predeployed by Katana",
+                    lookup.requested,
+                    accounts.len()
+                ))
+                .into(),
+            );
+        }
+    }
+    commands.insert_resource(DevAccounts(accounts));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc::Sender, time::Duration};
+
+    use super::*;
+
+    fn accounts(json: &str) -> Result<Vec<Felt>, String> {
+        serde_json::from_str::<RpcResponse>(json)
+            .unwrap()
+            .into_accounts()
+    }
+
+    #[test]
+    fn rpc_responses_are_mapped() {
+        assert_eq!(
+            accounts(r#"{"jsonrpc":"2.0","id":1,"result":[{"address":"0x7"},{"address":"0x8"}]}"#),
+            Ok(vec![Felt::from(7_u64), Felt::from(8_u64)])
+        );
+        assert_eq!(
+            accounts(
+                r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Method not found"}}"#
+            ),
+            Err("Method not found".to_string())
+        );
+        assert_eq!(
+            accounts(r#"{"jsonrpc":"2.0","id":1}"#),
+            Err("empty response".to_string())
+        );
+    }
+
+    #[test]
+    fn dev_accounts_resolve_by_index_or_address() {
+        let predeployed = [Felt::from(7_u64), Felt::from(8_u64)];
+
+        assert_eq!(DevAccount::Index(1).resolve(&predeployed), Some(1));
+        assert_eq!(DevAccount::Index(2).resolve(&predeployed), None);
+        assert_eq!(
+            DevAccount::Address(Felt::from(8_u64)).resolve(&predeployed),
+            Some(1)
+        );
+        assert_eq!(
+            DevAccount::Address(Felt::from(9_u64)).resolve(&predeployed),
+            None
+        );
+    }
+
+    fn lookup_app(requested: DevAccount) -> (App, Sender<Result<Vec<Felt>, String>>) {
+        let (sender, receiver) = channel();
+        let mut app = App::new();
+        app.init_resource::<DojoResource>()
+            .init_resource::<TokioRuntime>()
+            .add_event::<DojoErrorEvent>()
+            .insert_resource(PendingAccountConnection {
+                started_at: Duration::ZERO,
+                timeout: Duration::from_secs(10),
+            })
+            .insert_resource(DevAccountLookup {
+                requested,
+                katana_url: "http://localhost:5050".to_string(),
+                connect: matches!(requested, DevAccount::Address(_)),
+                receiver: Mutex::new(receiver),
+            })
+            .add_plugins(plugin);
+        (app, sender)
+    }
+
+    fn errors(app: &App) -> Vec<String> {
+        let events = app.world().resource::<Events<DojoErrorEvent>>();
+        events
+            .get_cursor()
+            .read(events)
+            .map(|DojoErrorEvent(error)| error.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn an_unavailable_dev_api_only_fails_the_address_lookup() {
+        let (mut app, sender) = lookup_app(DevAccount::Index(0));
+        sender.send(Err("Method not found".to_string())).unwrap();
+        app.update();
+
+        assert!(!app.world().contains_resource::<DevAccountLookup>());
+        assert!(app.world().contains_resource::<PendingAccountConnection>());
+        assert!(errors(&app).is_empty());
+
+        let (mut app, sender) = lookup_app(DevAccount::Address(Felt::from(8_u64)));
+        sender.send(Err("Method not found".to_string())).unwrap();
+        app.update();
+
+        assert!(!app.world().contains_resource::<PendingAccountConnection>());
+        assert_eq!(
+            errors(&app),
+            [DojoError::ConnectionFailed(
+                "could not list the predeployed accounts: Method not found".to_string()
+            )
+            .to_string()]
+        );
+    }
+
+    #[test]
+    fn an_unknown_address_fails_the_connection() {
+        let (mut app, sender) = lookup_app(DevAccount::Address(Felt::from(9_u64)));
+        sender.send(Ok(vec![Felt::from(7_u64)])).unwrap();
+        app.update();
+
+        assert!(!app.world().contains_resource::<PendingAccountConnection>());
+        assert_eq!(errors(&app).len(), 1);
+        assert_eq!(app.world().resource::<DevAccounts>().0, [Felt::from(7_u64)]);
+    }
+}
@@ -0,0 +1,145 @@
+//! Snapshots of what the client believes, written to JSON for bug reports.
+
+use bevy::prelude::*;
+use serde::Serialize;
+use starknet::core::types::Felt;
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    DojoSystemState, LOG_TARGET,
+    coordinates::GridPosition,
+    error::RecentErrors,
+    intro::{LocalPlayer, Player},
+    metrics::{DojoMetrics, DojoMetricsSnapshot},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<DumpStateConfig>();
+    app.add_event::<DumpState>();
+    app.add_systems(PostUpdate, dump_state.run_if(on_event::<DumpState>));
+}
+
+/// Writes the connection state, the players, the metrics and the recent errors to a
+/// `dojo-state-<unix seconds>.json` file. Sent by [`KeyBindings::dump_state`].
+///
+/// [`KeyBindings::dump_state`]: super::intro::KeyBindings::dump_state
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct DumpState;
+
+/// Where the [`DumpState`] files are written.
+#[derive(Resource, Debug, Clone)]
+pub struct DumpStateConfig {
+    pub directory: PathBuf,
+}
+
+impl Default for DumpStateConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("."),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StateDump {
+    /// Unix time of the dump, in seconds.
+    timestamp: u64,
+    connection: ConnectionDump,
+    players: Vec<PlayerDump>,
+    metrics: DojoMetricsSnapshot,
+    recent_errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ConnectionDump {
+    torii_connected: bool,
+    account_connected: bool,
+    account_connecting: bool,
+    wrong_chain: bool,
+    local_player_address: Option<Felt>,
+    latest_block: Option<u64>,
+    last_ping_ms: Option<u128>,
+    last_error: Option<String>,
+    /// The `Debug` output of the config, which redacts the secrets.
+    config: String,
+}
+
+/// Every field but the id is optional, a player missing a component is still dumped.
+#[derive(Serialize)]
+struct PlayerDump {
+    id: Felt,
+    local: bool,
+    translation: Option<[f32; 3]>,
+    cell: Option<[i32; 3]>,
+}
+
+fn dump_state(
+    config: Res<DumpStateConfig>,
+    dojo_state: Res<DojoSystemState>,
+    metrics: Res<DojoMetrics>,
+    recent_errors: Res<RecentErrors>,
+    players: Query<(
+        &Player,
+        Option<&Transform>,
+        Option<&GridPosition>,
+        Has<LocalPlayer>,
+    )>,
+    mut ev_dump_state: EventReader<DumpState>,
+) {
+    ev_dump_state.clear();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let mut players: Vec<PlayerDump> = players
+        .iter()
+        .map(|(player, transform, cell, local)| PlayerDump {
+            id: player.id,
+            local,
+            translation: transform.map(|transform| transform.translation.to_array()),
+            cell: cell.map(|GridPosition(cell)| cell.to_array()),
+        })
+        .collect();
+    // Stable across dumps, so two of them can be diffed.
+    players.sort_by_key(|player| player.id);
+
+    let dump = StateDump {
+        timestamp,
+        connection: ConnectionDump {
+            torii_connected: dojo_state.torii_connected,
+            account_connected: dojo_state.account_connected,
+            account_connecting: dojo_state.account_connecting,
+            wrong_chain: dojo_state.wrong_chain,
+            local_player_address: dojo_state.local_player_address,
+            latest_block: dojo_state.latest_block,
+            last_ping_ms: dojo_state.last_ping.map(|ping| ping.as_millis()),
+            last_error: dojo_state.last_error.clone(),
+            config: format!("{:?}", dojo_state.config),
+        },
+        players,
+        metrics: metrics.metrics_snapshot(),
+        recent_errors: recent_errors.iter().map(str::to_string).collect(),
+    };
+
+    let path = config
+        .directory
+        .join(format!("dojo-state-{timestamp}.json"));
+    let result = serde_json::to_string_pretty(&dump)
+        .map_err(io::Error::from)
+        .and_then(|json| fs::write(&path, json));
+    match result {
+        Ok(()) => info!(
+            target: LOG_TARGET,
+            path = %path.display(),
+            players = dump.players.len(),
+            "State dumped"
+        ),
+        Err(error) => {
+            error!(target: LOG_TARGET, path = %path.display(), %error, "Dumping the state failed");
+        }
+    }
+}
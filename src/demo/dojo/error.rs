@@ -0,0 +1,112 @@
+//! Errors reported by the Dojo systems.
+
+use bevy::prelude::*;
+use std::{collections::VecDeque, fmt};
+
+use super::{DojoSystemState, models::ModelParseError};
+use crate::constants::dojo::ConfigError;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<DojoErrorEvent>();
+    app.init_resource::<RecentErrors>();
+    // After every system that may report an error.
+    app.add_systems(PostUpdate, (record_dojo_errors, record_recent_errors));
+}
+
+/// Number of errors kept by [`RecentErrors`].
+const MAX_RECENT_ERRORS: usize = 16;
+
+/// Everything that can go wrong while talking to Katana and Torii.
+#[derive(Debug)]
+pub enum DojoError {
+    /// Torii or the account could not be reached, or serves the wrong network.
+    ConnectionFailed(String),
+    /// A model received from Torii doesn't match its Rust counterpart.
+    ModelParse {
+        model: String,
+        source: ModelParseError,
+    },
+    /// A transaction was rejected or reverted.
+    TxSubmit(String),
+    /// The [`DojoConfig`](crate::constants::dojo::DojoConfig) can't be used.
+    ConfigInvalid(Vec<ConfigError>),
+}
+
+impl fmt::Display for DojoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConnectionFailed(reason) => write!(f, "connection failed: {reason}"),
+            Self::ModelParse { model, source } => write!(f, "{model} model {source}"),
+            Self::TxSubmit(reason) => write!(f, "transaction failed: {reason}"),
+            Self::ConfigInvalid(errors) => {
+                let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                write!(f, "invalid configuration: {}", errors.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for DojoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ModelParse { source, .. } => Some(source),
+            Self::ConfigInvalid(errors) => errors
+                .first()
+                .map(|error| error as &(dyn std::error::Error + 'static)),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for DojoError {
+    fn from(error: ConfigError) -> Self {
+        Self::ConfigInvalid(vec![error])
+    }
+}
+
+/// This event will be triggered for every [`DojoError`], which is also stored in
+/// [`DojoSystemState::last_error`].
+#[derive(Event, Debug)]
+pub struct DojoErrorEvent(pub DojoError);
+
+impl From<DojoError> for DojoErrorEvent {
+    fn from(error: DojoError) -> Self {
+        Self(error)
+    }
+}
+
+/// The last errors reported, oldest first.
+#[derive(Resource, Debug, Default)]
+pub struct RecentErrors(VecDeque<String>);
+
+impl RecentErrors {
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn record_recent_errors(
+    mut recent: ResMut<RecentErrors>,
+    mut ev_error: EventReader<DojoErrorEvent>,
+) {
+    for DojoErrorEvent(error) in ev_error.read() {
+        recent.0.push_back(error.to_string());
+        if recent.0.len() > MAX_RECENT_ERRORS {
+            recent.0.pop_front();
+        }
+    }
+}
+
+fn record_dojo_errors(
+    mut dojo_state: ResMut<DojoSystemState>,
+    mut ev_error: EventReader<DojoErrorEvent>,
+) {
+    // `log_dojo_status` logs the error once stored.
+    if let Some(DojoErrorEvent(error)) = ev_error.read().last() {
+        dojo_state.last_error = Some(error.to_string());
+    }
+}
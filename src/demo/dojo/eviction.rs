@@ -0,0 +1,55 @@
+//! Caps the number of player cubes, for worlds too dense to render every player.
+
+use bevy::prelude::*;
+
+use super::{
+    LOG_TARGET,
+    animation::DespawnAnimation,
+    intro::{EntityTracker, LocalPlayer, PendingSpawn, Player, update_player_position},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        evict_least_recently_updated
+            .after(update_player_position)
+            .run_if(resource_exists::<RenderedEntityCap>),
+    );
+}
+
+/// Maximum number of player cubes. Every player is rendered without this resource.
+///
+/// Beyond it, the cubes of the players updated the least recently are despawned. Their player
+/// stays tracked and gets a cube again on its next position update. The local player is
+/// never evicted.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct RenderedEntityCap(pub usize);
+
+fn evict_least_recently_updated(
+    mut commands: Commands,
+    cap: Res<RenderedEntityCap>,
+    mut entity_tracker: ResMut<EntityTracker>,
+    players: Query<
+        (Entity, &Player, Has<LocalPlayer>),
+        (Without<PendingSpawn>, Without<DespawnAnimation>),
+    >,
+) {
+    let rendered = players.iter().count();
+    if rendered <= cap.0 {
+        return;
+    }
+
+    let mut candidates: Vec<(Entity, &Player)> = players
+        .iter()
+        .filter(|(_, _, is_local)| !is_local)
+        .map(|(entity, player, _)| (entity, player))
+        .collect();
+    candidates.sort_by_key(|(_, player)| entity_tracker.last_seen(player.id).unwrap_or_default());
+
+    let excess = rendered - cap.0;
+    for (entity, player) in candidates.into_iter().take(excess) {
+        debug!(target: LOG_TARGET, player = ?player.id, "Evicting player cube");
+        commands.entity(entity).despawn();
+        entity_tracker.evict(player.id);
+    }
+}
@@ -0,0 +1,113 @@
+//! Connects an account the player brings, instead of a Katana predeployed one.
+//!
+//! The account signs with a private key held in memory. Only use it with keys meant for this
+//! purpose (a session or burner account), never with a wallet holding real funds: anything
+//! able to read the process memory or environment can read the key. It's only read from the
+//! `ACCOUNT_PRIVATE_KEY` environment variable, never from `dojo.toml`, and never logged.
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::{DojoResource, TokioRuntime};
+use starknet::{
+    accounts::{ExecutionEncoding, SingleOwnerAccount},
+    core::types::Felt,
+    providers::{JsonRpcClient, Provider, jsonrpc::HttpTransport},
+    signers::{LocalWallet, SigningKey},
+};
+use std::sync::{
+    Arc, Mutex,
+    mpsc::{Receiver, channel},
+};
+use url::Url;
+
+use super::{
+    LOG_TARGET, PendingAccountConnection,
+    error::{DojoError, DojoErrorEvent},
+    task,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        receive_external_account.run_if(resource_exists::<ExternalAccountConnection>),
+    );
+}
+
+type ExternalAccount = SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>;
+
+/// An account being built, once the RPC told its chain id.
+#[derive(Resource)]
+pub struct ExternalAccountConnection {
+    receiver: Mutex<Receiver<Result<ExternalAccount, String>>>,
+}
+
+/// Connects the account at `address`, signing with `signing_key`.
+///
+/// The account is stored in the [`DojoResource`] like a predeployed one, so transactions are
+/// sent the same way whichever account is used.
+pub fn connect_external_account(
+    commands: &mut Commands,
+    tokio: &TokioRuntime,
+    rpc_url: &str,
+    address: Felt,
+    signing_key: SigningKey,
+) {
+    let url = match Url::parse(rpc_url) {
+        Ok(url) => url,
+        Err(error) => {
+            commands.send_event(DojoErrorEvent::from(DojoError::ConnectionFailed(format!(
+                "invalid RPC URL for the account: {error}"
+            ))));
+            return;
+        }
+    };
+
+    info!(target: LOG_TARGET, account = ?address, "Connecting external account");
+    let (sender, receiver) = channel();
+    task::spawn(tokio, async move {
+        let provider = JsonRpcClient::new(HttpTransport::new(url));
+        let result = match provider.chain_id().await {
+            Ok(chain_id) => Ok(SingleOwnerAccount::new(
+                provider,
+                LocalWallet::from(signing_key),
+                address,
+                chain_id,
+                ExecutionEncoding::New,
+            )),
+            Err(error) => Err(error.to_string()),
+        };
+        let _ = sender.send(result);
+    });
+
+    commands.insert_resource(ExternalAccountConnection {
+        receiver: Mutex::new(receiver),
+    });
+}
+
+fn receive_external_account(
+    mut commands: Commands,
+    connection: Res<ExternalAccountConnection>,
+    mut dojo: ResMut<DojoResource>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    let Some(result) = connection
+        .receiver
+        .lock()
+        .ok()
+        .and_then(|receiver| receiver.try_recv().ok())
+    else {
+        return;
+    };
+    commands.remove_resource::<ExternalAccountConnection>();
+
+    match result {
+        // Picked up by `watch_account_connection`, like a predeployed account.
+        Ok(account) => dojo.sn.account = Some(Arc::new(account)),
+        Err(error) => {
+            commands.remove_resource::<PendingAccountConnection>();
+            ev_error.write(
+                DojoError::ConnectionFailed(format!("could not connect the account: {error}"))
+                    .into(),
+            );
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Readable felts for the logs and the labels, e.g. `0x0585…a546` for a player address.
+
+use starknet::core::types::Felt;
+use std::fmt;
+
+/// Hex digits kept on each side by default.
+const DEFAULT_DIGITS: usize = 4;
+
+/// Formats a felt as its first and last hex digits, zero padded, like `0x0585…a546`. `Debug`
+/// prints the same, so it can be logged with `?` too.
+///
+/// ```ignore
+/// info!(target: LOG_TARGET, player = %player.short(), "Player spawned");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ShortFelt {
+    felt: Felt,
+    prefix: usize,
+    suffix: usize,
+}
+
+impl ShortFelt {
+    pub fn new(felt: Felt) -> Self {
+        Self {
+            felt,
+            prefix: DEFAULT_DIGITS,
+            suffix: DEFAULT_DIGITS,
+        }
+    }
+
+    /// Keeps `prefix` leading and `suffix` trailing hex digits. The whole felt is printed when
+    /// they cover it.
+    pub fn with_digits(self, prefix: usize, suffix: usize) -> Self {
+        Self {
+            prefix,
+            suffix,
+            ..self
+        }
+    }
+
+    pub fn felt(&self) -> Felt {
+        self.felt
+    }
+}
+
+impl From<Felt> for ShortFelt {
+    fn from(felt: Felt) -> Self {
+        Self::new(felt)
+    }
+}
+
+impl fmt::Display for ShortFelt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = self.felt.to_fixed_hex_string();
+        let digits = &hex[2..];
+        if self.prefix + self.suffix >= digits.len() {
+            return f.write_str(&hex);
+        }
+        write!(
+            f,
+            "0x{}…{}",
+            &digits[..self.prefix],
+            &digits[digits.len() - self.suffix..]
+        )
+    }
+}
+
+impl fmt::Debug for ShortFelt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+pub trait FeltExt {
+    /// See [`ShortFelt`].
+    fn short(&self) -> ShortFelt;
+}
+
+impl FeltExt for Felt {
+    fn short(&self) -> ShortFelt {
+        ShortFelt::new(*self)
+    }
+}
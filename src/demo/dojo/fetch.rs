@@ -0,0 +1,155 @@
+//! On-demand lookups of a single player, without fetching the whole world.
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::TokioRuntime;
+use dojo_types::schema::Struct;
+use starknet::core::types::Felt;
+use std::sync::{
+    Mutex,
+    mpsc::{Receiver, Sender, channel},
+};
+use torii_grpc_client::{WorldClient, types::schema::Entity};
+
+use super::{
+    DojoSystemState, LOG_TARGET,
+    error::{DojoError, DojoErrorEvent},
+    intro::Position,
+    query::DojoQueryBuilder,
+    registry::DojoModel,
+    task,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SingleEntityFetches>();
+    app.add_event::<FetchPlayerRequest>();
+    app.add_event::<SingleEntityFetched>();
+    app.add_systems(
+        Update,
+        fetch_requested_players
+            .run_if(on_event::<FetchPlayerRequest>)
+            .run_if(resource_exists::<TokioRuntime>),
+    );
+    app.add_systems(PreUpdate, receive_single_entities);
+}
+
+/// Requests the current position of a player, answered with a [`SingleEntityFetched`].
+///
+/// Only the entity keyed by the player is queried. Each request opens its own Torii connection,
+/// so it suits occasional lookups, e.g. when a player shows up in a menu, not every frame.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FetchPlayerRequest(pub Felt);
+
+/// This event will be triggered once for every [`FetchPlayerRequest`].
+#[derive(Event, Debug)]
+pub struct SingleEntityFetched {
+    pub player: Felt,
+    /// `None` when the player has no position, or the fetch failed.
+    pub position: Option<Position>,
+}
+
+/// The outcome of one fetch: the `Position` model if Torii has one, or the error.
+type FetchResult = (Felt, Result<Option<Struct>, String>);
+
+#[derive(Resource)]
+struct SingleEntityFetches {
+    sender: Sender<FetchResult>,
+    receiver: Mutex<Receiver<FetchResult>>,
+}
+
+impl Default for SingleEntityFetches {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+fn fetch_requested_players(
+    tokio: Res<TokioRuntime>,
+    dojo_state: Res<DojoSystemState>,
+    fetches: Res<SingleEntityFetches>,
+    mut ev_fetch: EventReader<FetchPlayerRequest>,
+) {
+    for FetchPlayerRequest(player) in ev_fetch.read().copied() {
+        let torii_url = dojo_state.config.torii_url.clone();
+        let world_address = dojo_state.config.world_address;
+        let timeout = dojo_state.config.request_timeout;
+        let model = dojo_state.config.model_name(Position::NAME);
+        let sender = fetches.sender.clone();
+        task::spawn(&tokio, async move {
+            let fetch = fetch_position(torii_url, world_address, model, player);
+            let result = task::timeout(timeout, fetch)
+                .await
+                .and_then(|result| result);
+            let _ = sender.send((player, result));
+        });
+    }
+}
+
+async fn fetch_position(
+    torii_url: String,
+    world_address: Felt,
+    model: String,
+    player: Felt,
+) -> Result<Option<Struct>, String> {
+    let mut client = WorldClient::new(torii_url, world_address)
+        .await
+        .map_err(|error| error.to_string())?;
+
+    let query = DojoQueryBuilder::new()
+        .limit(1)
+        .keys(vec![Some(player)], [model.as_str()])
+        .models([model.as_str()])
+        .build();
+    let response = client
+        .retrieve_entities(query)
+        .await
+        .map_err(|error| error.to_string())?;
+
+    let Some(entity) = response.entities.into_iter().next() else {
+        return Ok(None);
+    };
+    let entity = Entity::try_from(entity).map_err(|error| error.to_string())?;
+    Ok(entity.models.into_iter().find(|found| found.name == model))
+}
+
+fn receive_single_entities(
+    fetches: Res<SingleEntityFetches>,
+    mut ev_fetched: EventWriter<SingleEntityFetched>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    let results: Vec<FetchResult> = fetches
+        .receiver
+        .lock()
+        .map(|receiver| receiver.try_iter().collect())
+        .unwrap_or_default();
+
+    for (player, result) in results {
+        let position = match result {
+            Ok(model) => model.and_then(|model| match Position::try_from(&model) {
+                Ok(position) => Some(position),
+                Err(source) => {
+                    ev_error.write(
+                        DojoError::ModelParse {
+                            model: model.name.clone(),
+                            source,
+                        }
+                        .into(),
+                    );
+                    None
+                }
+            }),
+            Err(error) => {
+                ev_error.write(
+                    DojoError::ConnectionFailed(format!("fetching {player:#x} failed: {error}"))
+                        .into(),
+                );
+                None
+            }
+        };
+        debug!(target: LOG_TARGET, ?player, found = position.is_some(), "Player fetched");
+        ev_fetched.write(SingleEntityFetched { player, position });
+    }
+}
@@ -0,0 +1,77 @@
+//! Drive the player with a controller, alongside the keyboard.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use super::actions::PlayerAction;
+use crate::AppSystems;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<GamepadConfig>();
+    app.add_systems(Update, handle_gamepad_input.in_set(AppSystems::RecordInput));
+}
+
+/// Controller settings.
+#[derive(Resource, Debug)]
+pub struct GamepadConfig {
+    /// The left stick is ignored below this magnitude, so stick drift doesn't queue moves.
+    pub deadzone: f32,
+    pub spawn_button: GamepadButton,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.5,
+            spawn_button: GamepadButton::South,
+        }
+    }
+}
+
+/// D-pad and shoulder buttons in the order expected by the `move` entrypoint.
+const DPAD_DIRECTIONS: [GamepadButton; 6] = [
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadRight,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::RightTrigger,
+    GamepadButton::LeftTrigger,
+];
+
+/// Returns the direction the stick points to, if it's outside the deadzone.
+fn stick_direction(stick: Vec2, deadzone: f32) -> Option<u8> {
+    if stick.length() < deadzone {
+        return None;
+    }
+    if stick.x.abs() > stick.y.abs() {
+        Some(if stick.x < 0.0 { 0 } else { 1 })
+    } else {
+        Some(if stick.y > 0.0 { 2 } else { 3 })
+    }
+}
+
+fn handle_gamepad_input(
+    config: Res<GamepadConfig>,
+    gamepads: Query<(Entity, &Gamepad)>,
+    // The last direction of each stick, so a move is only queued when it changes.
+    mut stick_directions: Local<HashMap<Entity, Option<u8>>>,
+    mut ev_player_action: EventWriter<PlayerAction>,
+) {
+    for (entity, gamepad) in &gamepads {
+        if gamepad.just_pressed(config.spawn_button) {
+            ev_player_action.write(PlayerAction::Spawn);
+        }
+
+        let dpad_direction = DPAD_DIRECTIONS
+            .iter()
+            .position(|button| gamepad.just_pressed(*button))
+            .map(|index| index as u8);
+
+        let stick = stick_direction(gamepad.left_stick(), config.deadzone);
+        let previous_stick = stick_directions.insert(entity, stick).flatten();
+        let stick_direction = stick.filter(|direction| previous_stick != Some(*direction));
+
+        if let Some(direction) = dpad_direction.or(stick_direction) {
+            ev_player_action.write(PlayerAction::Move(direction));
+        }
+    }
+}
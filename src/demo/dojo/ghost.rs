@@ -0,0 +1,83 @@
+//! A translucent cube at the last authoritative position of the local player, to see how far
+//! the prediction runs ahead of Torii.
+
+use bevy::prelude::*;
+use starknet::core::types::Felt;
+
+use super::{
+    appearance::PlayerAppearance,
+    intro::{LocalPlayer, Player, update_player_position},
+    prediction::Prediction,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShowPredictionGhost>();
+    app.add_systems(
+        Update,
+        (
+            update_prediction_ghost
+                .after(update_player_position)
+                .run_if(|show: Res<ShowPredictionGhost>| show.0),
+            despawn_prediction_ghost.run_if(
+                resource_changed::<ShowPredictionGhost>
+                    .and(|show: Res<ShowPredictionGhost>| !show.0),
+            ),
+        ),
+    );
+}
+
+/// How opaque the ghost is.
+const GHOST_ALPHA: f32 = 0.3;
+
+/// Shows the [`PredictionGhost`]. Disabled by default.
+#[derive(Resource, Debug, Default)]
+pub struct ShowPredictionGhost(pub bool);
+
+/// The ghost of the [`LocalPlayer`], the solid cube showing the predicted position.
+#[derive(Component, Debug)]
+pub struct PredictionGhost {
+    pub player: Felt,
+}
+
+fn update_prediction_ghost(
+    mut commands: Commands,
+    appearance: Res<PlayerAppearance>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    local_player: Query<(&Player, &Prediction), With<LocalPlayer>>,
+    mut ghosts: Query<(Entity, &PredictionGhost, &mut Transform)>,
+) {
+    let local_player = local_player.single().ok();
+    let mut has_ghost = false;
+    for (entity, ghost, mut transform) in &mut ghosts {
+        match local_player {
+            Some((player, prediction)) if player.id == ghost.player => {
+                transform.translation = prediction.authoritative;
+                has_ghost = true;
+            }
+            // The local player changed, e.g. with the account, or is gone.
+            _ => commands.entity(entity).despawn(),
+        }
+    }
+
+    let Some((player, prediction)) = local_player.filter(|_| !has_ghost) else {
+        return;
+    };
+    let mut material = appearance.material(player.id);
+    material.base_color = material.base_color.with_alpha(GHOST_ALPHA);
+    material.alpha_mode = AlphaMode::Blend;
+    commands.spawn((
+        Name::new("Prediction Ghost"),
+        PredictionGhost { player: player.id },
+        Mesh3d(appearance.mesh(&mut meshes)),
+        MeshMaterial3d(materials.add(material)),
+        Transform::from_translation(prediction.authoritative),
+        Pickable::IGNORE,
+    ));
+}
+
+fn despawn_prediction_ghost(mut commands: Commands, ghosts: Query<Entity, With<PredictionGhost>>) {
+    for entity in &ghosts {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,56 @@
+//! Grid lines at the onchain coordinates, to see the cubes snap to cells.
+
+use bevy::prelude::*;
+
+use super::coordinates::CoordinateScale;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<GridConfig>();
+    app.add_systems(
+        Update,
+        draw_world_grid.run_if(|config: Res<GridConfig>| config.visible),
+    );
+}
+
+/// Controls the world grid. Hidden by default.
+#[derive(Resource, Debug)]
+pub struct GridConfig {
+    /// Coordinates covered on each side of the origin, along `x` and `y`.
+    pub size: u32,
+    /// Coordinates between two lines, 1 drawing one at every coordinate.
+    pub spacing: u32,
+    pub visible: bool,
+    pub color: Color,
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            size: 10,
+            spacing: 1,
+            visible: false,
+            color: Color::srgba(1.0, 1.0, 1.0, 0.15),
+        }
+    }
+}
+
+/// Draws the lines in the `z = 0` plane, where the positions without depth live.
+fn draw_world_grid(mut gizmos: Gizmos, config: Res<GridConfig>, scale: Res<CoordinateScale>) {
+    let spacing = config.spacing.max(1);
+    // Lines are kept at multiples of `spacing`, so one always goes through the origin.
+    let lines = (config.size / spacing) as i32;
+    let extent = config.size as f32;
+    for line in -lines..=lines {
+        let coordinate = (line * spacing as i32) as f32;
+        gizmos.line(
+            scale.to_world(Vec3::new(coordinate, -extent, 0.0)),
+            scale.to_world(Vec3::new(coordinate, extent, 0.0)),
+            config.color,
+        );
+        gizmos.line(
+            scale.to_world(Vec3::new(-extent, coordinate, 0.0)),
+            scale.to_world(Vec3::new(extent, coordinate, 0.0)),
+            config.color,
+        );
+    }
+}
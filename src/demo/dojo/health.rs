@@ -0,0 +1,204 @@
+//! Periodic pings to Torii, to measure the latency and detect a lost connection.
+//!
+//! Torii is still pinged once marked as disconnected, and connected again as soon as it
+//! answers.
+
+use bevy::{platform::time::Instant, prelude::*};
+use dojo_bevy_plugin::{DojoResource, TokioRuntime};
+use std::{
+    sync::{
+        Arc, Mutex,
+        mpsc::{Receiver, Sender, channel},
+    },
+    time::Duration,
+};
+use torii_grpc_client::WorldClient;
+
+use super::{
+    DojoDisconnected, DojoSystemState, LOG_TARGET,
+    error::{DojoError, DojoErrorEvent},
+    query::DojoQueryBuilder,
+    task,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ToriiHealthCheck>();
+    app.init_resource::<PingState>();
+    app.add_systems(
+        Update,
+        (
+            reset_ping_state.run_if(on_event::<DojoDisconnected>),
+            (torii_health_check, receive_torii_pings)
+                .chain()
+                .run_if(resource_exists::<TokioRuntime>)
+                .run_if(should_ping),
+        )
+            .chain(),
+    );
+}
+
+/// How often Torii is pinged, and how many failed pings mark it as disconnected.
+#[derive(Resource, Debug)]
+pub struct ToriiHealthCheck {
+    pub interval: Duration,
+    pub max_failures: u32,
+}
+
+impl Default for ToriiHealthCheck {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            max_failures: 3,
+        }
+    }
+}
+
+/// The outcome of one ping: its round-trip time, or the error.
+type PingResult = Result<Duration, String>;
+
+#[derive(Resource)]
+struct PingState {
+    since_last_ping: Duration,
+    in_flight: bool,
+    consecutive_failures: u32,
+    /// Set once the pings marked Torii as disconnected, until one succeeds again.
+    torii_lost: bool,
+    /// Kept between pings, so only the query is measured.
+    client: Arc<tokio::sync::Mutex<Option<WorldClient>>>,
+    sender: Sender<PingResult>,
+    receiver: Mutex<Receiver<PingResult>>,
+}
+
+impl Default for PingState {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            since_last_ping: Duration::ZERO,
+            in_flight: false,
+            consecutive_failures: 0,
+            torii_lost: false,
+            client: Arc::default(),
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+/// Torii is also pinged once lost, to notice when it's back.
+fn should_ping(dojo_state: Res<DojoSystemState>, ping_state: Res<PingState>) -> bool {
+    dojo_state.torii_connected || ping_state.torii_lost
+}
+
+/// Issues a limit-1 query every [`ToriiHealthCheck::interval`].
+fn torii_health_check(
+    time: Res<Time>,
+    tokio: Res<TokioRuntime>,
+    health_check: Res<ToriiHealthCheck>,
+    dojo_state: Res<DojoSystemState>,
+    mut ping_state: ResMut<PingState>,
+) {
+    ping_state.since_last_ping += time.delta();
+    if ping_state.in_flight || ping_state.since_last_ping < health_check.interval {
+        return;
+    }
+    ping_state.since_last_ping = Duration::ZERO;
+    ping_state.in_flight = true;
+
+    let client = ping_state.client.clone();
+    let sender = ping_state.sender.clone();
+    let torii_url = dojo_state.config.torii_url.clone();
+    let world_address = dojo_state.config.world_address;
+    let timeout = dojo_state.config.request_timeout;
+    task::spawn(&tokio, async move {
+        let mut client = client.lock().await;
+        if client.is_none() {
+            match task::timeout(timeout, WorldClient::new(torii_url, world_address)).await {
+                Ok(Ok(connected)) => *client = Some(connected),
+                Ok(Err(error)) => {
+                    let _ = sender.send(Err(error.to_string()));
+                    return;
+                }
+                Err(error) => {
+                    let _ = sender.send(Err(error));
+                    return;
+                }
+            }
+        }
+        let Some(client) = client.as_mut() else {
+            return;
+        };
+
+        // A stuck Torii counts as a failed ping, rather than keeping the ping in flight forever.
+        let started_at = Instant::now();
+        let query = DojoQueryBuilder::new().limit(1).build();
+        let result = task::timeout(timeout, client.retrieve_entities(query))
+            .await
+            .and_then(|result| result.map_err(|error| error.to_string()))
+            .map(|_| started_at.elapsed());
+        let _ = sender.send(result);
+    });
+}
+
+fn receive_torii_pings(
+    health_check: Res<ToriiHealthCheck>,
+    tokio: Res<TokioRuntime>,
+    mut dojo: ResMut<DojoResource>,
+    mut dojo_state: ResMut<DojoSystemState>,
+    mut ping_state: ResMut<PingState>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    let results: Vec<PingResult> = ping_state
+        .receiver
+        .lock()
+        .map(|receiver| receiver.try_iter().collect())
+        .unwrap_or_default();
+
+    for result in results {
+        ping_state.in_flight = false;
+        match result {
+            Ok(latency) => {
+                debug!(target: LOG_TARGET, ?latency, "Torii ping");
+                ping_state.consecutive_failures = 0;
+                dojo_state.last_ping = Some(latency);
+                dojo_state.last_ping_at = Some(Instant::now());
+                if ping_state.torii_lost {
+                    // Torii sends `DojoInitializedEvent` again, which fetches the world and
+                    // subscribes again.
+                    info!(target: LOG_TARGET, "Torii reachable again, reconnecting");
+                    ping_state.torii_lost = false;
+                    let config = &dojo_state.config;
+                    dojo.connect_torii(&tokio, config.torii_url.clone(), config.world_address);
+                    dojo_state.torii_connected = true;
+                }
+            }
+            Err(error) => {
+                ping_state.consecutive_failures += 1;
+                warn!(
+                    target: LOG_TARGET,
+                    failures = ping_state.consecutive_failures,
+                    "Torii ping failed: {}", error
+                );
+                // A broken client is rebuilt on the next ping.
+                if let Ok(mut client) = ping_state.client.try_lock() {
+                    *client = None;
+                }
+                if !ping_state.torii_lost
+                    && ping_state.consecutive_failures >= health_check.max_failures
+                {
+                    error!(target: LOG_TARGET, "Torii unreachable, marking it as disconnected");
+                    ping_state.torii_lost = true;
+                    dojo_state.torii_connected = false;
+                    dojo_state.last_ping = None;
+                    ev_error.write(
+                        DojoError::ConnectionFailed(format!("Torii unreachable: {error}")).into(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Forgets the client, which may point to the previous world.
+fn reset_ping_state(mut ping_state: ResMut<PingState>) {
+    *ping_state = PingState::default();
+}
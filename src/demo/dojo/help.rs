@@ -0,0 +1,108 @@
+//! On-screen list of the controls, built from the current [`KeyBindings`].
+
+use bevy::{prelude::*, ui::Val::*};
+
+use super::{hud, intro::KeyBindings};
+use crate::theme::palette::LABEL_TEXT;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Startup, spawn_help_overlay);
+    app.add_systems(
+        Update,
+        (
+            update_help_overlay.run_if(resource_changed::<KeyBindings>),
+            toggle_help_overlay.run_if(
+                |input: Res<ButtonInput<KeyCode>>, key_bindings: Res<KeyBindings>| {
+                    input.just_pressed(key_bindings.help)
+                },
+            ),
+        ),
+    );
+}
+
+#[derive(Component)]
+struct HelpOverlay;
+
+#[derive(Component)]
+struct HelpText;
+
+fn spawn_help_overlay(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Help Overlay"),
+        HelpOverlay,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Px(10.0),
+            left: Px(10.0),
+            padding: UiRect::all(Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        BorderRadius::all(Px(6.0)),
+        GlobalZIndex(1),
+        Pickable::IGNORE,
+        children![(
+            Name::new("Help Text"),
+            HelpText,
+            Text::default(),
+            TextFont::from_font_size(14.0),
+            TextColor(LABEL_TEXT),
+        )],
+    ));
+}
+
+fn update_help_overlay(
+    key_bindings: Res<KeyBindings>,
+    mut help_text: Query<&mut Text, With<HelpText>>,
+) {
+    let [left, right, up, down, z_up, z_down] = key_bindings
+        .move_dirs
+        .map(|key_code| key_code.map(key_label).unwrap_or_else(|| "-".to_string()));
+    let mut lines = vec![(format!("{left} {right} {up} {down}"), "Move")];
+    // Only worlds tracking `z` bind these.
+    let [.., z_up_key, z_down_key] = key_bindings.move_dirs;
+    if z_up_key.is_some() || z_down_key.is_some() {
+        lines.push((format!("{z_up} {z_down}"), "Move along z"));
+    }
+    lines.extend([
+        (key_label(key_bindings.spawn), "Spawn"),
+        (key_label(key_bindings.subscribe), "Subscribe to updates"),
+        (key_label(key_bindings.resync), "Resync the world"),
+        (key_label(hud::TOGGLE_KEY), "Toggle the status"),
+        (
+            key_label(key_bindings.dump_state),
+            "Dump the state to a file",
+        ),
+        (key_label(key_bindings.help), "Toggle this help"),
+    ]);
+    let text = lines
+        .iter()
+        .map(|(keys, action)| format!("{keys}: {action}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    for mut help in &mut help_text {
+        help.0.clone_from(&text);
+    }
+}
+
+fn toggle_help_overlay(mut overlay: Query<&mut Visibility, With<HelpOverlay>>) {
+    for mut visibility in &mut overlay {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// A short name for a key, e.g. `S` for `KeyS` or `Left` for `ArrowLeft`.
+///
+/// Kept ASCII, which the default font covers.
+fn key_label(key_code: KeyCode) -> String {
+    let name = format!("{key_code:?}");
+    ["Key", "Digit", "Arrow"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(&name)
+        .to_string()
+}
@@ -0,0 +1,129 @@
+//! On-screen panel showing the Dojo connection status.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use super::{ConnectionStatus, DojoSystemState};
+use crate::theme::palette::LABEL_TEXT;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Startup, spawn_status_hud);
+    app.add_systems(
+        Update,
+        (
+            update_status_hud.run_if(resource_changed::<DojoSystemState>),
+            toggle_status_hud.run_if(input_just_pressed(TOGGLE_KEY)),
+        ),
+    );
+}
+
+pub(super) const TOGGLE_KEY: KeyCode = KeyCode::F1;
+
+/// Red used for the error line.
+const ERROR_TEXT: Color = Color::srgb(0.9, 0.25, 0.25);
+
+#[derive(Component)]
+struct StatusHud;
+
+#[derive(Component)]
+struct StatusDot;
+
+#[derive(Component)]
+struct StatusText;
+
+#[derive(Component)]
+struct StatusErrorText;
+
+fn spawn_status_hud(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Dojo Status HUD"),
+        StatusHud,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Px(10.0),
+            right: Px(10.0),
+            padding: UiRect::all(Px(8.0)),
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(4.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        BorderRadius::all(Px(6.0)),
+        GlobalZIndex(1),
+        Pickable::IGNORE,
+        children![
+            (
+                Name::new("Status Row"),
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Px(6.0),
+                    ..default()
+                },
+                children![
+                    (
+                        Name::new("Status Dot"),
+                        StatusDot,
+                        Node {
+                            width: Px(10.0),
+                            height: Px(10.0),
+                            ..default()
+                        },
+                        BorderRadius::MAX,
+                        BackgroundColor(Color::NONE),
+                    ),
+                    (
+                        Name::new("Status Text"),
+                        StatusText,
+                        Text::default(),
+                        TextFont::from_font_size(14.0),
+                        TextColor(LABEL_TEXT),
+                    ),
+                ],
+            ),
+            (
+                Name::new("Status Error Text"),
+                StatusErrorText,
+                Text::default(),
+                TextFont::from_font_size(12.0),
+                TextColor(ERROR_TEXT),
+            ),
+        ],
+    ));
+}
+
+fn update_status_hud(
+    dojo_state: Res<DojoSystemState>,
+    mut dot: Query<&mut BackgroundColor, With<StatusDot>>,
+    mut status_text: Query<&mut Text, (With<StatusText>, Without<StatusErrorText>)>,
+    mut error_text: Query<&mut Text, (With<StatusErrorText>, Without<StatusText>)>,
+) {
+    let status = ConnectionStatus::from_state(&dojo_state);
+
+    for mut background in &mut dot {
+        background.0 = status.color();
+    }
+    // A local Katana is the default, only the remote networks are named.
+    let network = dojo_state.config.network();
+    let label = if network.is_local() {
+        status.label().to_string()
+    } else {
+        format!("[{network}] {}", status.label())
+    };
+    for mut text in &mut status_text {
+        text.0 = match dojo_state.latest_block {
+            Some(block) => format!("{label} - synced to block {block}"),
+            None => label.clone(),
+        };
+    }
+    for mut text in &mut error_text {
+        text.0 = dojo_state.last_error.clone().unwrap_or_default();
+    }
+}
+
+fn toggle_status_hud(mut hud: Query<&mut Visibility, With<StatusHud>>) {
+    for mut visibility in &mut hud {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
@@ -0,0 +1,117 @@
+//! An egui panel showing the Dojo state, with buttons for the keyboard actions.
+//!
+//! Only built with the `dev-inspector` feature, so release builds don't pull egui.
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPlugin, egui};
+
+use super::{
+    DojoSystemState, SwitchWorld,
+    actions::PlayerAction,
+    coordinates::GridPosition,
+    error::RecentErrors,
+    intro::{EntityTracker, LocalPlayer, Player, ResyncRequest},
+    subscription::SubscribeRequest,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    if !app.is_plugin_added::<EguiPlugin>() {
+        app.add_plugins(EguiPlugin {
+            enable_multipass_for_primary_context: false,
+        });
+    }
+    app.add_systems(Update, draw_inspector);
+}
+
+fn draw_inspector(
+    mut contexts: EguiContexts,
+    dojo_state: Res<DojoSystemState>,
+    entity_tracker: Res<EntityTracker>,
+    recent_errors: Res<RecentErrors>,
+    players: Query<(&Player, Option<&GridPosition>, Has<LocalPlayer>)>,
+    mut ev_player_action: EventWriter<PlayerAction>,
+    mut ev_subscribe: EventWriter<SubscribeRequest>,
+    mut ev_resync: EventWriter<ResyncRequest>,
+    mut ev_switch: EventWriter<SwitchWorld>,
+) {
+    egui::Window::new("Dojo Inspector").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            if ui.button("Spawn").clicked() {
+                ev_player_action.write(PlayerAction::Spawn);
+            }
+            if ui.button("Subscribe").clicked() {
+                ev_subscribe.write(SubscribeRequest);
+            }
+            if ui.button("Resync").clicked() {
+                ev_resync.write(ResyncRequest);
+            }
+            // Switching to the current world drops and re-establishes every connection.
+            if ui.button("Reconnect").clicked() {
+                ev_switch.write(SwitchWorld {
+                    config: dojo_state.config.clone(),
+                });
+            }
+        });
+
+        ui.collapsing("State", |ui| {
+            ui.label(format!("Torii connected: {}", dojo_state.torii_connected));
+            ui.label(format!(
+                "Account connected: {}",
+                dojo_state.account_connected
+            ));
+            ui.label(format!(
+                "Account connecting: {}",
+                dojo_state.account_connecting
+            ));
+            ui.label(format!("Wrong chain: {}", dojo_state.wrong_chain));
+            match dojo_state.local_player_address {
+                Some(address) => ui.label(format!("Local player: {address:#x}")),
+                None => ui.label("Local player: none"),
+            };
+            match dojo_state.last_ping {
+                Some(ping) => ui.label(format!("Last ping: {ping:?}")),
+                None => ui.label("Last ping: none"),
+            };
+            ui.label(format!("World: {:#x}", dojo_state.config.world_address));
+        });
+
+        ui.collapsing("Entity tracker", |ui| {
+            ui.label(format!(
+                "{} players spawned",
+                entity_tracker.existing_entities().count()
+            ));
+            for (entity_id, player) in entity_tracker.players_by_entity() {
+                ui.monospace(format!("{entity_id:#x} -> {player:#x}"));
+            }
+        });
+
+        ui.collapsing("Players", |ui| {
+            egui::Grid::new("dojo_inspector_players")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (player, grid_position, is_local) in &players {
+                        let name = format!("{:#x}", player.id);
+                        ui.monospace(if is_local {
+                            format!("{name} (local)")
+                        } else {
+                            name
+                        });
+                        match grid_position {
+                            Some(GridPosition(position)) => ui.label(position.to_string()),
+                            None => ui.label("pending"),
+                        };
+                        ui.end_row();
+                    }
+                });
+        });
+
+        ui.collapsing("Recent errors", |ui| {
+            if recent_errors.is_empty() {
+                ui.label("None");
+            }
+            for error in recent_errors.iter().rev() {
+                ui.label(error);
+            }
+        });
+    });
+}
@@ -1,223 +1,1189 @@
-use crate::constants::dojo::{MOVE_SELECTOR, SPAWN_SELECTOR};
-
+use super::LOG_TARGET;
+use super::actions::PlayerAction;
+use super::animation::{
+    DespawnAnimation, DespawnAnimationSettings, SpawnAnimation, SpawnAnimationSettings,
+};
+use super::appearance::PlayerAppearance;
+use super::coordinates::{CoordinateScale, GridPosition, OutOfBounds, WorldBounds};
+use super::dump::DumpState;
+use super::error::{DojoError, DojoErrorEvent};
+use super::felt::FeltExt;
+use super::metrics::DojoMetrics;
+use super::models::{ModelParseError, model_values};
+use super::pause::{DojoPaused, PauseConfig, PausedUpdates};
+use super::registry::{DojoModel, ModelComponents, ModelRegistry, RawModelUpdated};
+use super::subscription::{SubscribeRequest, SubscriptionReady, is_subscription_sentinel};
+use super::sync::{SyncOrder, start_initial_sync};
+use super::throttle::{DeferredUpdates, MaxUpdatesPerFrame, UpdateChannelCapacity};
+use super::tx::TxReverted;
+use crate::AppSystems;
 use bevy::{
     input::{ButtonState, keyboard::KeyboardInput},
     prelude::*,
 };
-use dojo_bevy_plugin::{DojoEntityUpdated, DojoInitializedEvent, DojoResource, TokioRuntime};
+use dojo_bevy_plugin::{DojoEntityUpdated, DojoInitializedEvent, TokioRuntime};
 use dojo_types::schema::Struct;
-use starknet::core::types::{Call, Felt};
-use std::collections::HashSet;
-use torii_grpc_client::types::{Pagination, PaginationDirection, Query as ToriiQuery};
+use starknet::core::types::Felt;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 /// This event will be triggered every time the position is updated.
 #[derive(Event)]
-pub struct PositionUpdatedEvent(pub Position);
+pub struct PositionUpdatedEvent {
+    pub position: Position,
+    /// Block the update comes from, when known.
+    ///
+    /// Always `None` for now: `DojoEntityUpdated` only carries the entity id and its models,
+    /// and the Torii entities fetched by the initial sync don't include a block number either.
+    /// Filling it needs the plugin to forward the block of each update.
+    pub block: Option<u64>,
+}
+
+/// Emitted by `update_player_position` when an already spawned player changes cell, unlike
+/// [`PositionUpdatedEvent`] which also fires for the first position and for no-op updates.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerMovedEvent {
+    pub player: Felt,
+    /// World positions of the previous and the new cell.
+    pub from: Vec3,
+    pub to: Vec3,
+    /// The move in cells, e.g. `IVec3::X` for one step right.
+    pub delta: IVec3,
+}
+
+/// Requests a client-side resync: every player is despawned and the world fetched again.
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ResyncRequest;
+
+/// This event will be triggered every time the remaining moves of a player are updated.
+#[derive(Event)]
+pub struct MovesUpdatedEvent(pub Moves);
+
+/// This event will be triggered when a player's entity is removed from Torii.
+#[derive(Event)]
+pub struct PlayerDespawnedEvent(pub Felt);
 
 #[derive(Resource, Default)]
-struct EntityTracker {
+pub(super) struct EntityTracker {
     existing_entities: HashSet<Felt>,
+    /// Torii entity ids (hashed keys) mapped to the player they hold the position of.
+    players_by_entity: HashMap<Felt, Felt>,
+    /// Elapsed app time of the last position update of each player.
+    last_seen: HashMap<Felt, Duration>,
+}
+
+impl EntityTracker {
+    /// Players that got a cube.
+    pub(super) fn existing_entities(&self) -> impl Iterator<Item = Felt> + '_ {
+        self.existing_entities.iter().copied()
+    }
+
+    /// Torii entity ids with the player they hold the position of.
+    pub(super) fn players_by_entity(&self) -> impl Iterator<Item = (Felt, Felt)> + '_ {
+        self.players_by_entity
+            .iter()
+            .map(|(entity_id, player)| (*entity_id, *player))
+    }
+
+    /// The player whose position the Torii entity holds, once Torii sent it.
+    pub(super) fn player_of(&self, entity_id: Felt) -> Option<Felt> {
+        self.players_by_entity.get(&entity_id).copied()
+    }
+
+    pub(super) fn last_seen(&self, player: Felt) -> Option<Duration> {
+        self.last_seen.get(&player).copied()
+    }
+
+    /// Marks the cube of a player as gone, while it's still part of the world. The next
+    /// position update spawns it again.
+    pub(super) fn evict(&mut self, player: Felt) {
+        self.existing_entities.remove(&player);
+    }
+
+    /// Forgets a player whose cube was despawned.
+    pub(super) fn forget(&mut self, player: Felt) {
+        self.existing_entities.remove(&player);
+        self.last_seen.remove(&player);
+        self.players_by_entity
+            .retain(|_, tracked| *tracked != player);
+    }
+}
+
+/// The entity of each [`Player`], pending ones included, for direct lookups by address.
+///
+/// Kept up to date by observers whenever a `Player` is added or removed, so despawns from any
+/// path (animations, resyncs, world switches) are reflected without extra bookkeeping. Entities
+/// spawned this frame only show up once the commands are applied.
+#[derive(Resource, Debug, Default)]
+pub struct EntityIndex {
+    entities: HashMap<Felt, Entity>,
+}
+
+impl EntityIndex {
+    pub fn get(&self, player: Felt) -> Option<Entity> {
+        self.entities.get(&player).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Felt, Entity)> + '_ {
+        self.entities
+            .iter()
+            .map(|(player, entity)| (*player, *entity))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+fn index_player(
+    trigger: Trigger<OnAdd, Player>,
+    players: Query<&Player>,
+    mut index: ResMut<EntityIndex>,
+) {
+    if let Ok(player) = players.get(trigger.target()) {
+        index.entities.insert(player.id, trigger.target());
+    }
+}
+
+fn unindex_player(
+    trigger: Trigger<OnRemove, Player>,
+    players: Query<&Player>,
+    mut index: ResMut<EntityIndex>,
+) {
+    let Ok(player) = players.get(trigger.target()) else {
+        return;
+    };
+    // Another entity may have taken over the address in the meantime.
+    if index.get(player.id) == Some(trigger.target()) {
+        index.entities.remove(&player.id);
+    }
 }
 
 /// A very simple cube to represent the player.
+///
+/// It's the single entity of the player, found by address in the [`EntityIndex`]: every model
+/// registered as a component, e.g. with
+/// [`RegisterModelExt::register_model`](super::registry::RegisterModelExt::register_model), is
+/// upserted on it, so the models of a player are queried together, e.g. with
+/// `Query<(&Player, &Position, &Moves)>`.
 #[derive(Component)]
 pub struct Player {
     pub id: Felt,
+    /// Elapsed app time of the last position update, see [`StaleThreshold`].
+    ///
+    /// [`StaleThreshold`]: super::stale::StaleThreshold
+    pub last_update: Duration,
 }
 
+/// A [`Player`] known from another model, waiting for its first position to get a cube.
+///
+/// It stays hidden until then, instead of flashing at the origin.
+#[derive(Component, Debug)]
+pub struct PendingSpawn;
+
+/// Marks the [`Player`] controlled by the connected account.
+#[derive(Component, Debug)]
+pub struct LocalPlayer;
+
+/// Smoothly moves a player cube toward its last known onchain position.
+#[derive(Component, Debug)]
+pub struct PlayerInterpolation {
+    pub target: Vec3,
+    /// How fast the cube catches up with `target`, in fractions of the distance per second.
+    pub lerp_speed: f32,
+}
+
+/// Controls the smoothing applied to player movement.
+#[derive(Resource, Debug)]
+pub struct InterpolationSettings {
+    /// Applied to each [`PlayerInterpolation`] when a new position arrives.
+    pub lerp_speed: f32,
+}
+
+impl Default for InterpolationSettings {
+    fn default() -> Self {
+        Self { lerp_speed: 10.0 }
+    }
+}
+
+/// Keys used to interact with the Dojo world. Mutating this resource rebinds them at runtime.
+#[derive(Resource, Debug, Clone)]
+pub struct KeyBindings {
+    pub spawn: KeyCode,
+    pub subscribe: KeyCode,
+    /// Despawns every player and fetches the world again, without reconnecting.
+    pub resync: KeyCode,
+    /// Shows or hides the list of controls.
+    pub help: KeyCode,
+    /// Writes what the client knows to a JSON file, see [`DumpState`].
+    pub dump_state: KeyCode,
+    /// Keys for the left, right, up, down, z-up and z-down directions, in the order of
+    /// [`PlayerAction::Move`]. The z directions are unbound by default, the `Direction` enum of
+    /// the `actions` contract only having the first four: bind them, e.g. to Q and E, along
+    /// with the [`DirectionMap`](super::actions::DirectionMap), for a world tracking `z`.
+    pub move_dirs: [Option<KeyCode>; 6],
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            spawn: KeyCode::Space,
+            subscribe: KeyCode::KeyS,
+            resync: KeyCode::KeyR,
+            help: KeyCode::KeyH,
+            dump_state: KeyCode::F12,
+            move_dirs: [
+                Some(KeyCode::ArrowLeft),
+                Some(KeyCode::ArrowRight),
+                Some(KeyCode::ArrowUp),
+                Some(KeyCode::ArrowDown),
+                None,
+                None,
+            ],
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Maps a key to its [`PlayerAction::Move`] direction.
+    pub fn direction_for(&self, key_code: KeyCode) -> Option<u8> {
+        self.move_dirs
+            .iter()
+            .position(|bound| *bound == Some(key_code))
+            .map(|index| index as u8)
+    }
+}
+
+/// Re-queues the move call while a movement key is held down.
+///
+/// Movement is edge-triggered when this resource is absent or disabled.
+#[derive(Resource, Debug)]
+pub struct HeldMovementConfig {
+    pub enabled: bool,
+    /// Delay between two moves, never shorter than [`MIN_HELD_MOVE_INTERVAL`].
+    pub repeat_interval: Duration,
+}
+
+impl Default for HeldMovementConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            repeat_interval: Duration::from_millis(250),
+        }
+    }
+}
+
+/// Lower bound of [`HeldMovementConfig::repeat_interval`], to avoid flooding Katana with transactions.
+pub const MIN_HELD_MOVE_INTERVAL: Duration = Duration::from_millis(100);
+
 pub(super) fn plugin(app: &mut App) {
+    // Both models are also stored on the player entity, so they can be queried together.
+    Position::register(app);
+    Moves::register(app);
+
     app.init_resource::<EntityTracker>()
+        .init_resource::<EntityIndex>()
+        .add_observer(index_player)
+        .add_observer(unindex_player)
+        .init_resource::<KeyBindings>()
+        .init_resource::<InterpolationSettings>()
         .add_event::<PositionUpdatedEvent>()
+        .add_event::<MovesUpdatedEvent>()
+        .add_event::<PlayerMovedEvent>()
+        .add_event::<PlayerDespawnedEvent>()
+        .add_event::<ResyncRequest>()
         .add_systems(
             Update,
             (
-                handle_keyboard_input,
+                (handle_keyboard_input, repeat_held_movement)
+                    .chain()
+                    .in_set(AppSystems::RecordInput),
+                resync_world
+                    .run_if(on_event::<ResyncRequest>)
+                    .run_if(resource_exists::<TokioRuntime>)
+                    .before(on_dojo_events),
                 on_dojo_events,
-                (update_player_position).after(on_dojo_events),
+                // Models are stored on the player entity, possibly spawned by a position update.
+                (
+                    update_player_position,
+                    update_player_moves,
+                    attach_model_components,
+                )
+                    .chain()
+                    .after(on_dojo_events),
+                interpolate_players.after(update_player_position),
+                despawn_players.after(attach_model_components),
+                explain_reverts,
+                tag_local_player.after(update_player_position).run_if(
+                    |state: Res<super::DojoSystemState>| state.local_player_address.is_some(),
+                ),
             ),
         );
 }
 
 /// This system is responsible for handling the keyboard input.
 fn handle_keyboard_input(
-    tokio: Res<TokioRuntime>,
-    mut dojo: ResMut<DojoResource>,
-    dojo_config: Res<super::DojoSystemState>,
+    key_bindings: Res<KeyBindings>,
     mut keyboard_input_events: EventReader<KeyboardInput>,
+    mut ev_player_action: EventWriter<PlayerAction>,
+    mut ev_subscribe: EventWriter<SubscribeRequest>,
+    mut ev_resync: EventWriter<ResyncRequest>,
+    mut ev_dump_state: EventWriter<DumpState>,
 ) {
     for event in keyboard_input_events.read() {
         let key_code = event.key_code;
         let is_pressed = event.state == ButtonState::Pressed;
 
         match key_code {
-            KeyCode::Space if is_pressed => {
-                info!("Spawning.");
-                let calls = vec![Call {
-                    to: dojo_config.config.action_address,
-                    selector: SPAWN_SELECTOR,
-                    calldata: vec![],
-                }];
-
-                dojo.queue_tx(&tokio, calls);
+            _ if key_code == key_bindings.spawn && is_pressed && !event.repeat => {
+                ev_player_action.write(PlayerAction::Spawn);
             }
-            KeyCode::KeyS if is_pressed => {
-                info!("Setting up Torii subscription.");
-                dojo.subscribe_entities(&tokio, "position".to_string(), None);
+            _ if key_code == key_bindings.subscribe && is_pressed => {
+                ev_subscribe.write(SubscribeRequest);
             }
-            KeyCode::ArrowLeft | KeyCode::ArrowRight | KeyCode::ArrowUp | KeyCode::ArrowDown
-                if is_pressed =>
-            {
-                let direction = match key_code {
-                    KeyCode::ArrowLeft => 0,
-                    KeyCode::ArrowRight => 1,
-                    KeyCode::ArrowUp => 2,
-                    KeyCode::ArrowDown => 3,
-                    _ => panic!("Invalid key code"),
-                };
-
-                let calls = vec![Call {
-                    to: dojo_config.config.action_address,
-                    selector: MOVE_SELECTOR,
-                    calldata: vec![Felt::from(direction)],
-                }];
-
-                dojo.queue_tx(&tokio, calls);
+            _ if key_code == key_bindings.resync && is_pressed && !event.repeat => {
+                ev_resync.write(ResyncRequest);
+            }
+            _ if key_code == key_bindings.dump_state && is_pressed && !event.repeat => {
+                ev_dump_state.write(DumpState);
+            }
+            // OS key repeats are ignored, held keys are handled by `repeat_held_movement`.
+            _ if is_pressed && !event.repeat => {
+                if let Some(direction) = key_bindings.direction_for(key_code) {
+                    ev_player_action.write(PlayerAction::Move(direction));
+                }
             }
             _ => continue,
         }
     }
 }
 
+/// Queues another move every `repeat_interval` while a movement key stays down.
+fn repeat_held_movement(
+    time: Res<Time>,
+    held_movement: Option<Res<HeldMovementConfig>>,
+    key_bindings: Res<KeyBindings>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut held_for: Local<Duration>,
+    mut ev_player_action: EventWriter<PlayerAction>,
+) {
+    let Some(held_movement) = held_movement.filter(|config| config.enabled) else {
+        return;
+    };
+
+    let Some(direction) = input
+        .get_pressed()
+        .find_map(|key_code| key_bindings.direction_for(*key_code))
+    else {
+        *held_for = Duration::ZERO;
+        return;
+    };
+
+    // The initial press is already handled by `handle_keyboard_input`.
+    if input
+        .get_just_pressed()
+        .any(|key_code| key_bindings.direction_for(*key_code).is_some())
+    {
+        *held_for = Duration::ZERO;
+        return;
+    }
+
+    *held_for += time.delta();
+    let interval = held_movement.repeat_interval.max(MIN_HELD_MOVE_INTERVAL);
+    if *held_for >= interval {
+        *held_for -= interval;
+        ev_player_action.write(PlayerAction::Move(direction));
+    }
+}
+
 /// Updates the cube position by reacting to the dedicated event
 /// for new position updates.
-fn update_player_position(
+///
+/// The player entities are the source of truth: the [`EntityTracker`] is repaired if it lost
+/// track of a player, instead of spawning a second cube for it.
+pub(super) fn update_player_position(
     mut commands: Commands,
+    time: Res<Time>,
+    mut dojo_state: ResMut<super::DojoSystemState>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut entity_tracker: ResMut<EntityTracker>,
+    interpolation: Res<InterpolationSettings>,
+    scale: Res<CoordinateScale>,
+    bounds: Res<WorldBounds>,
+    appearance: Res<PlayerAppearance>,
+    spawn_animation: Res<SpawnAnimationSettings>,
+    entity_index: Res<EntityIndex>,
     mut ev_position_updated: EventReader<PositionUpdatedEvent>,
-    mut query: Query<(&mut Transform, &Player)>,
+    mut ev_player_moved: EventWriter<PlayerMovedEvent>,
+    mut query: Query<(
+        &mut Player,
+        &mut PlayerInterpolation,
+        &mut GridPosition,
+        &mut Visibility,
+    )>,
+    pending: Query<(), With<PendingSpawn>>,
 ) {
+    // Spawns are deferred, so the query doesn't see the players spawned by this run yet.
+    let mut spawned: HashMap<Felt, Entity> = HashMap::default();
+
     for ev in ev_position_updated.read() {
-        let Position { x, y, player } = ev.0;
-
-        if !entity_tracker.existing_entities.contains(&player) {
-            commands.spawn((
-                Mesh3d(meshes.add(Cuboid::new(0.5, 0.5, 0.5))),
-                MeshMaterial3d(materials.add(Color::srgb(0.8, 0.2, 0.2))),
-                Player { id: player },
-                Transform::from_xyz(x as f32, y as f32, 0.0),
-            ));
+        let player = ev.position.player;
+        let now = time.elapsed();
+        entity_tracker.last_seen.insert(player, now);
+        if let Some(block) = ev
+            .block
+            .filter(|block| dojo_state.latest_block < Some(*block))
+        {
+            dojo_state.latest_block = Some(block);
+        }
+        let cell = ev.position.as_ivec3();
+        let in_bounds = bounds.contains(cell);
+        let hidden = !in_bounds && bounds.out_of_bounds == OutOfBounds::Hide;
+        if !in_bounds {
+            warn!(
+                target: LOG_TARGET,
+                ?player,
+                ?cell,
+                min = ?bounds.min,
+                max = ?bounds.max,
+                "Position outside the world bounds, {}",
+                if hidden { "hiding the player" } else { "clamping it" }
+            );
+        }
+        let grid_position = GridPosition(bounds.clamp(cell));
+        let target = scale.to_world(grid_position.0.as_vec3());
+        let visibility = if hidden {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        let player_interpolation = PlayerInterpolation {
+            target,
+            lerp_speed: interpolation.lerp_speed,
+        };
 
+        let indexed = entity_index.get(player);
+        let existing = indexed.and_then(|entity| query.get_mut(entity).ok());
+        if let Some((
+            mut current_player,
+            mut current_interpolation,
+            mut current_grid_position,
+            mut current_visibility,
+        )) = existing
+        {
+            if entity_tracker.existing_entities.insert(player) {
+                debug!(
+                    target: LOG_TARGET,
+                    ?player,
+                    "Player already spawned, repairing the tracker"
+                );
+            }
+            let delta = grid_position.0 - current_grid_position.0;
+            if delta != IVec3::ZERO {
+                ev_player_moved.write(PlayerMovedEvent {
+                    player,
+                    from: scale.to_world(current_grid_position.0.as_vec3()),
+                    to: target,
+                    delta,
+                });
+            }
+            current_player.last_update = now;
+            *current_interpolation = player_interpolation;
+            *current_grid_position = grid_position;
+            current_visibility.set_if_neq(visibility);
+        } else if let Some(entity) = spawned.get(&player) {
+            commands
+                .entity(*entity)
+                .insert((player_interpolation, grid_position, visibility));
+        } else {
+            // Freshly spawned players snap to their position.
+            let cube = (
+                Player {
+                    id: player,
+                    last_update: now,
+                },
+                Mesh3d(appearance.mesh(&mut meshes)),
+                MeshMaterial3d(materials.add(appearance.material(player))),
+                Transform::from_translation(target),
+                player_interpolation,
+                grid_position,
+                visibility,
+            );
+            let mut entity = match indexed.filter(|entity| pending.contains(*entity)) {
+                Some(entity) => {
+                    let mut entity = commands.entity(entity);
+                    entity.remove::<PendingSpawn>().insert(cube);
+                    entity
+                }
+                None => commands.spawn(cube),
+            };
+            if let Some(animation) = SpawnAnimation::from_settings(&spawn_animation) {
+                entity.insert((
+                    animation,
+                    Transform::from_translation(target).with_scale(Vec3::ZERO),
+                ));
+            }
+
+            spawned.insert(player, entity.id());
             entity_tracker.existing_entities.insert(player);
+        }
+    }
+}
+
+/// Tags the player matching the connected account.
+///
+/// Runs every frame rather than on position updates, since the first position can arrive
+/// before the account address is known.
+fn tag_local_player(
+    mut commands: Commands,
+    dojo_state: Res<super::DojoSystemState>,
+    query: Query<(Entity, &Player), (Without<LocalPlayer>, Without<PendingSpawn>)>,
+) {
+    let Some(address) = dojo_state.local_player_address else {
+        return;
+    };
+
+    for (entity, player) in &query {
+        if player.id == address {
+            info!(target: LOG_TARGET, player = ?player.id, "Local player found");
+            commands.entity(entity).insert(LocalPlayer);
+        }
+    }
+}
+
+/// Explains the reverts caused by the player, with a hint when they can fix it.
+fn explain_reverts(
+    dojo_state: Res<super::DojoSystemState>,
+    key_bindings: Res<KeyBindings>,
+    mut ev_reverted: EventReader<TxReverted>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    for ev in ev_reverted.read() {
+        let call = if ev.call_selector == dojo_state.config.move_selector {
+            "move"
+        } else if ev.call_selector == dojo_state.config.spawn_selector {
+            "spawn"
         } else {
-            for (mut transform, player_comp) in query.iter_mut() {
-                if player_comp.id == player {
-                    transform.translation = Vec3::new(x as f32, y as f32, 0.0);
+            "transaction"
+        };
+
+        let reason = ev.reason.to_lowercase();
+        let not_spawned = reason.contains("not spawned") || reason.contains("no moves");
+        let message = if call == "move" && not_spawned {
+            format!(
+                "{call} reverted: {} — press {:?} first",
+                ev.reason, key_bindings.spawn
+            )
+        } else {
+            format!("{call} reverted: {}", ev.reason)
+        };
+
+        ev_error.write(DojoError::TxSubmit(message).into());
+    }
+}
+
+/// The palette of Okabe and Ito, which colorblind users can tell apart, for [`ColorblindSafe`].
+///
+/// Its black is left out, it wouldn't show on the scene.
+///
+/// [`ColorblindSafe`]: super::appearance::ColorblindSafe
+pub const OKABE_ITO_PALETTE: [Color; 7] = [
+    // Orange.
+    Color::srgb(0.902, 0.624, 0.0),
+    // Sky blue.
+    Color::srgb(0.337, 0.706, 0.914),
+    // Bluish green.
+    Color::srgb(0.0, 0.620, 0.451),
+    // Yellow.
+    Color::srgb(0.941, 0.894, 0.259),
+    // Blue.
+    Color::srgb(0.0, 0.447, 0.698),
+    // Vermillion.
+    Color::srgb(0.835, 0.369, 0.0),
+    // Reddish purple.
+    Color::srgb(0.800, 0.475, 0.655),
+];
+
+/// Picks a stable color for a player, so the same address always gets the same color.
+///
+/// With `colorblind_safe`, the color is the one of [`OKABE_ITO_PALETTE`] at the address modulo
+/// its length. Otherwise it's a hue derived from the address, which may be hard to tell apart
+/// from another one, e.g. red and green.
+pub fn color_for_player(id: Felt, colorblind_safe: bool) -> Color {
+    if colorblind_safe {
+        let len = OKABE_ITO_PALETTE.len();
+        let index = id
+            .to_bytes_be()
+            .iter()
+            .fold(0, |rem, byte| (rem * 256 + usize::from(*byte)) % len);
+        return OKABE_ITO_PALETTE[index];
+    }
+
+    // FNV-1a, which unlike `DefaultHasher` is guaranteed to be stable across Rust releases.
+    let hash = id
+        .to_bytes_be()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    let hue = (hash % 360) as f32;
+
+    Color::hsl(hue, 0.7, 0.55)
+}
+
+/// Moves each player cube a step closer to its target every frame.
+pub(super) fn interpolate_players(
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &PlayerInterpolation)>,
+) {
+    for (mut transform, interpolation) in &mut query {
+        let t = (interpolation.lerp_speed * time.delta_secs()).clamp(0.0, 1.0);
+        transform.translation = transform.translation.lerp(interpolation.target, t);
+    }
+}
+
+/// Spawns the player of the moves received before its position, which holds its player, so
+/// they don't wait for the position in the [`ModelComponents`] like the other models.
+fn update_player_moves(
+    mut commands: Commands,
+    time: Res<Time>,
+    entity_index: Res<EntityIndex>,
+    mut ev_moves_updated: EventReader<MovesUpdatedEvent>,
+) {
+    // Spawns are deferred, so the index doesn't see the players spawned by this run yet.
+    let mut spawned: HashMap<Felt, Entity> = HashMap::default();
+    for ev in ev_moves_updated.read() {
+        let moves = &ev.0;
+
+        // The known players get it as the component of `Moves`.
+        if entity_index.get(moves.player).is_some() {
+            continue;
+        }
+        match spawned.get(&moves.player) {
+            Some(&entity) => {
+                commands.entity(entity).insert(moves.clone());
+            }
+            None => {
+                debug!(
+                    target: LOG_TARGET,
+                    player = %moves.player.short(),
+                    "Moves received for a player without position"
+                );
+                let entity = commands
+                    .spawn((
+                        Name::new("Pending Player"),
+                        Player {
+                            id: moves.player,
+                            last_update: time.elapsed(),
+                        },
+                        moves.clone(),
+                        PendingSpawn,
+                        Transform::default(),
+                        Visibility::Hidden,
+                    ))
+                    .id();
+                spawned.insert(moves.player, entity);
+            }
+        }
+    }
+}
+
+/// Inserts the models registered with `register_model` on the entity of their player.
+///
+/// A Torii entity is matched to its player by its `Position`, so the components of entities
+/// whose position wasn't received yet are kept for the next frames, up to
+/// [`ModelComponents::timeout`].
+fn attach_model_components(
+    mut commands: Commands,
+    time: Res<Time>,
+    entity_tracker: Res<EntityTracker>,
+    entity_index: Res<EntityIndex>,
+    mut model_components: ResMut<ModelComponents>,
+) {
+    if model_components.is_empty() {
+        return;
+    }
+
+    // Spawns are deferred, so the index doesn't see the players spawned by this run yet.
+    let mut spawned: HashMap<Felt, Entity> = HashMap::default();
+    let dropped = model_components.prune(time.elapsed());
+    if dropped > 0 {
+        debug!(target: LOG_TARGET, dropped, "Dropped models whose position never arrived");
+    }
+
+    let mut waiting = Vec::new();
+    for pending in model_components.take() {
+        let Some(player) = entity_tracker.player_of(pending.entity_id) else {
+            waiting.push(pending);
+            continue;
+        };
+
+        let entity = match entity_index
+            .get(player)
+            .or_else(|| spawned.get(&player).copied())
+        {
+            Some(entity) => entity,
+            // Tracked, but without a cube, e.g. after an eviction.
+            None => {
+                debug!(target: LOG_TARGET, ?player, "Model received for a player without cube");
+                let entity = commands
+                    .spawn((
+                        Name::new("Pending Player"),
+                        Player {
+                            id: player,
+                            last_update: time.elapsed(),
+                        },
+                        PendingSpawn,
+                        Transform::default(),
+                        Visibility::Hidden,
+                    ))
+                    .id();
+                spawned.insert(player, entity);
+                entity
+            }
+        };
+        (pending.insert)(&mut commands.entity(entity));
+    }
+    model_components.set(waiting);
+}
+
+/// Removes the cube of each player that left the world.
+///
+/// With a [`DespawnAnimation`], the cube is only despawned, and the player forgotten, once it
+/// faded out. A position update in the meantime cancels the animation.
+fn despawn_players(
+    mut commands: Commands,
+    mut entity_tracker: ResMut<EntityTracker>,
+    despawn_animation: Res<DespawnAnimationSettings>,
+    entity_index: Res<EntityIndex>,
+    mut ev_player_despawned: EventReader<PlayerDespawnedEvent>,
+    pending: Query<(), With<PendingSpawn>>,
+) {
+    for ev in ev_player_despawned.read() {
+        let animation = DespawnAnimation::from_settings(&despawn_animation);
+        if let Some(entity) = entity_index.get(ev.0) {
+            // Without a cube, there is nothing to animate.
+            match animation.filter(|_| !pending.contains(entity)) {
+                Some(animation) => {
+                    commands
+                        .entity(entity)
+                        .remove::<SpawnAnimation>()
+                        .insert(animation);
                 }
+                None => commands.entity(entity).despawn(),
             }
         }
+
+        if animation.is_none() {
+            entity_tracker.forget(ev.0);
+        }
     }
 }
 
+/// Forgets every player and restarts the initial sync, keeping the current connections.
+fn resync_world(
+    mut commands: Commands,
+    tokio: Res<TokioRuntime>,
+    dojo_state: Res<super::DojoSystemState>,
+    registry: Res<ModelRegistry>,
+    mut entity_tracker: ResMut<EntityTracker>,
+    (channel_capacity, sync_order, mut deferred): (
+        Res<UpdateChannelCapacity>,
+        Res<SyncOrder>,
+        ResMut<DeferredUpdates>,
+    ),
+    mut ev_resync: EventReader<ResyncRequest>,
+    players: Query<Entity, With<Player>>,
+) {
+    ev_resync.clear();
+    info!(target: LOG_TARGET, "Resyncing the world from Torii.");
+
+    for entity in &players {
+        commands.entity(entity).despawn();
+    }
+    *entity_tracker = EntityTracker::default();
+    deferred.clear();
+
+    start_initial_sync(
+        &mut commands,
+        &tokio,
+        &dojo_state,
+        channel_capacity.0,
+        &sync_order,
+        registry.model_names(),
+    );
+}
+
 /// Reacts on Dojo events, which are emitted by the Dojo plugin.
 ///
-/// Any `queue_retrieve_entities` or `subscribe_entities` call will trigger
-/// the `DojoEntityUpdated` event.
+/// Any `queue_retrieve_entities` or `subscribe_entities` call, as well as the initial sync,
+/// will trigger the `DojoEntityUpdated` event.
 fn on_dojo_events(
-    mut dojo: ResMut<DojoResource>,
-    tokio: Res<TokioRuntime>,
+    mut commands: Commands,
+    registry: Res<ModelRegistry>,
+    mut metrics: ResMut<DojoMetrics>,
+    mut dojo_state: ResMut<super::DojoSystemState>,
+    mut entity_tracker: ResMut<EntityTracker>,
+    mut model_components: ResMut<ModelComponents>,
+    tokio: Option<Res<TokioRuntime>>,
+    (channel_capacity, sync_order): (Res<UpdateChannelCapacity>, Res<SyncOrder>),
+    (paused, pause_config, mut paused_updates): (
+        Res<DojoPaused>,
+        Res<PauseConfig>,
+        ResMut<PausedUpdates>,
+    ),
+    (max_updates, mut deferred): (Res<MaxUpdatesPerFrame>, ResMut<DeferredUpdates>),
     mut ev_initialized: EventReader<DojoInitializedEvent>,
     mut ev_retrieve_entities: EventReader<DojoEntityUpdated>,
-    mut ev_position_updated: EventWriter<PositionUpdatedEvent>,
+    mut ev_player_despawned: EventWriter<PlayerDespawnedEvent>,
+    mut ev_raw_model: EventWriter<RawModelUpdated>,
+    mut ev_subscription_ready: EventWriter<SubscriptionReady>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
 ) {
     for _ in ev_initialized.read() {
-        info!("Dojo initialized.");
-
-        // Initial fetch, which will make the Dojo plugin to send
-        // the query Torii, and trigger the `DojoEntityUpdated` event.
-        dojo.queue_retrieve_entities(
-            &tokio,
-            ToriiQuery {
-                clause: None,
-                pagination: Pagination {
-                    limit: 100,
-                    cursor: None,
-                    direction: PaginationDirection::Forward,
-                    order_by: vec![],
-                },
-                no_hashed_keys: false,
-                models: vec![],
-                historical: false,
-            },
+        info!(target: LOG_TARGET, "Dojo initialized.");
+        clear_last_error(&mut dojo_state);
+
+        // Initial fetch, which pages through Torii and triggers the `DojoEntityUpdated` event
+        // for every entity. Only the registered models are fetched, keyed by the player address.
+        // Replays and tests may drive the updates without a runtime.
+        if let Some(tokio) = &tokio {
+            start_initial_sync(
+                &mut commands,
+                tokio,
+                &dojo_state,
+                channel_capacity.0,
+                &sync_order,
+                registry.model_names(),
+            );
+        }
+    }
+
+    if paused.0 {
+        for ev in ev_retrieve_entities.read() {
+            paused_updates.push(ev, pause_config.max_buffered_updates);
+        }
+        return;
+    }
+    if paused_updates.dropped > 0 {
+        warn!(
+            target: LOG_TARGET,
+            dropped = paused_updates.dropped,
+            "Torii updates dropped while paused"
+        );
+    }
+    // The updates carried from the previous frames come first, being the oldest.
+    let buffered = paused_updates.drain();
+    for update in buffered.iter().chain(ev_retrieve_entities.read()) {
+        metrics.entity_updates_received += 1;
+        if deferred.push(update) {
+            metrics.updates_coalesced += 1;
+        }
+    }
+
+    let updates = deferred.drain_front(max_updates.0.max(1));
+    if !deferred.is_empty() {
+        debug!(
+            target: LOG_TARGET,
+            deferred = deferred.len(),
+            "Deferring Torii updates to the next frames"
         );
     }
 
-    // Since the deserialization of the models is project specific,
-    // currently the way it is done is by emitting an event for each
-    // models updates we are interested in.
-    // This may become too much for a large number of models though.
-    // Maybe the solution would be to generate a plugin via bindgen,
-    // that registers all of this automatically.
-    for ev in ev_retrieve_entities.read() {
-        info!(entity_id = ?ev.entity_id, "Torii update");
+    let position_model = dojo_state.config.model_name(Position::NAME);
+    // Since the deserialization of the models is project specific, each model is handed to
+    // the handler registered for it in the `ModelRegistry`, which emits the matching event.
+    // Maybe the solution would be to generate the handlers via bindgen.
+    for ev in &updates {
+        let _span = debug_span!(
+            target: LOG_TARGET,
+            "entity_update",
+            entity_id = %ev.entity_id.short(),
+            models = ev.models.len()
+        )
+        .entered();
+        info!(target: LOG_TARGET, "Torii update");
 
-        // Felt::ZERO is being emitted once, when the subcription is initialized.
-        // We don't want to spawn a cube for this.
-        if ev.entity_id == Felt::ZERO {
+        // Not an entity, so no cube is spawned for it.
+        if is_subscription_sentinel(ev.entity_id) {
+            info!(target: LOG_TARGET, "Torii subscription live");
+            ev_subscription_ready.write(SubscriptionReady);
+            continue;
+        }
+
+        // Torii signals a deleted entity by sending it without any model.
+        if ev.models.is_empty() {
+            model_components.forget(ev.entity_id);
+            if let Some(player) = entity_tracker.players_by_entity.remove(&ev.entity_id) {
+                info!(
+                    target: LOG_TARGET,
+                    entity_id = %ev.entity_id.short(),
+                    player = %player.short(),
+                    "Player removed"
+                );
+                ev_player_despawned.write(PlayerDespawnedEvent(player));
+            }
             continue;
         }
 
         for m in &ev.models {
-            debug!("model: {:?}", &m);
+            debug!(target: LOG_TARGET, "model: {:?}", &m);
+            ev_raw_model.write(RawModelUpdated {
+                entity_id: ev.entity_id,
+                model_name: m.name.clone(),
+                fields: model_values(m),
+            });
 
-            match m.name.as_str() {
-                "di-Position" => {
-                    ev_position_updated.write(PositionUpdatedEvent(m.into()));
+            let Some(handler) = registry.get(&m.name) else {
+                warn!(target: LOG_TARGET, "Model not handled: {:?}", m);
+                continue;
+            };
+            match handler(ev.entity_id, m, &mut commands) {
+                Ok(()) => {
+                    if m.name == position_model {
+                        metrics.positions_parsed += 1;
+                    }
+                    clear_last_error(&mut dojo_state);
                 }
-                name if name == "di-Moves".to_string() => {}
-                _ => {
-                    warn!("Model not handled: {:?}", m);
+                Err(error) => {
+                    metrics.parse_errors += 1;
+                    warn!(
+                        target: LOG_TARGET,
+                        entity_id = %ev.entity_id.short(),
+                        "Skipping {} update: {}",
+                        m.name,
+                        error
+                    );
+                    ev_error.write(
+                        DojoError::ModelParse {
+                            model: m.name.clone(),
+                            source: error,
+                        }
+                        .into(),
+                    );
                 }
-            };
+            }
         }
     }
 }
 
+/// Turns a `di-Position` model into a [`PositionUpdatedEvent`].
+fn handle_position(
+    entity_id: Felt,
+    model: &Struct,
+    commands: &mut Commands,
+) -> Result<(), ModelParseError> {
+    let position = Position::try_from(model)?;
+    let player = position.player;
+    commands.queue(move |world: &mut World| {
+        world
+            .resource_mut::<EntityTracker>()
+            .players_by_entity
+            .insert(entity_id, player);
+    });
+    commands.send_event(PositionUpdatedEvent {
+        position,
+        block: None,
+    });
+    Ok(())
+}
+
+/// Clears the last error without triggering change detection when there is none.
+fn clear_last_error(dojo_state: &mut ResMut<super::DojoSystemState>) {
+    if dojo_state.last_error.is_some() {
+        dojo_state.last_error = None;
+    }
+}
+
 /// The position of the player in the game.
-#[derive(Component, Debug)]
+#[derive(Component, Debug, DojoModel)]
+#[dojo(name = "di-Position", handler = handle_position, component)]
 pub struct Position {
+    #[dojo(as = "contract_address")]
     pub player: Felt,
-    pub x: u32,
-    pub y: u32,
-}
-
-/// This implementation shows a manual way to map data from the Position model in Cairo.
-/// Ideally, we want a binding generation to do that for us.
-impl From<&Struct> for Position {
-    fn from(struct_value: &Struct) -> Self {
-        let player = struct_value
-            .get("player")
-            .unwrap()
-            .as_primitive()
-            .unwrap()
-            .as_contract_address()
-            .unwrap();
-        let x = struct_value
-            .get("x")
-            .unwrap()
-            .as_primitive()
-            .unwrap()
-            .as_u32()
-            .unwrap();
-        let y = struct_value
-            .get("y")
-            .unwrap()
-            .as_primitive()
-            .unwrap()
-            .as_u32()
-            .unwrap();
-
-        Position { player, x, y }
+    #[dojo(as = "coordinate")]
+    pub x: i32,
+    #[dojo(as = "coordinate")]
+    pub y: i32,
+    /// Always 0 for 2D worlds, whose model has no `z` field.
+    #[dojo(as = "coordinate", default)]
+    pub z: i32,
+}
+
+impl Position {
+    pub fn as_ivec2(&self) -> IVec2 {
+        IVec2::new(self.x, self.y)
+    }
+
+    pub fn as_ivec3(&self) -> IVec3 {
+        IVec3::new(self.x, self.y, self.z)
+    }
+
+    pub fn as_vec3(&self) -> Vec3 {
+        self.as_ivec3().as_vec3()
+    }
+}
+
+/// The remaining moves of the player in the game.
+#[derive(Component, Debug, Clone, DojoModel)]
+#[dojo(name = "di-Moves", event = MovesUpdatedEvent, component)]
+pub struct Moves {
+    #[dojo(as = "contract_address")]
+    pub player: Felt,
+    /// Stored as a `u8` in Cairo.
+    #[dojo(as = "u8")]
+    pub remaining: u32,
+    /// Index of the last `Direction` variant the player moved in, if any.
+    #[dojo(as = "option_enum")]
+    pub last_direction: Option<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::demo::dojo::mock;
+
+    fn hue(color: Color) -> f32 {
+        Hsla::from(color).hue
+    }
+
+    #[test]
+    fn player_color_is_stable() {
+        let player = Felt::from_hex_unchecked(
+            "0x127fd5f1fe78a71f8bcd1fec63e3fe2f0486b6ecd5c86a0466c3a21fa5cfcec",
+        );
+        assert_eq!(
+            color_for_player(player, false),
+            color_for_player(player, false)
+        );
+        assert_eq!(
+            color_for_player(player, true),
+            color_for_player(player, true)
+        );
+    }
+
+    #[test]
+    fn players_get_distinct_hues() {
+        let hues: Vec<f32> = (1..=8_u64)
+            .map(|id| hue(color_for_player(Felt::from(id), false)))
+            .collect();
+        for (i, a) in hues.iter().enumerate() {
+            for b in &hues[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    /// Fails unless the index holds exactly the players of the `Player` query.
+    fn assert_index_matches_players(app: &mut App) {
+        let mut players: Vec<(Felt, Entity)> = app
+            .world_mut()
+            .query::<(Entity, &Player)>()
+            .iter(app.world())
+            .map(|(entity, player)| (player.id, entity))
+            .collect();
+        let mut indexed: Vec<(Felt, Entity)> =
+            app.world().resource::<EntityIndex>().iter().collect();
+        players.sort();
+        indexed.sort();
+        assert_eq!(indexed, players);
+    }
+
+    #[test]
+    fn entity_index_follows_spawns_despawns_and_resyncs() {
+        let mut app = mock::headless_app();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            100,
+        )));
+        app.update();
+
+        for id in 1..=200_u32 {
+            let player = Felt::from(id);
+            app.world_mut().send_event(mock::entity_updated(
+                player,
+                vec![mock::position_struct(player, id, 0)],
+            ));
+        }
+        app.update();
+        assert_eq!(app.world().resource::<EntityIndex>().len(), 200);
+        assert_index_matches_players(&mut app);
+
+        // Every other player leaves, while the others move.
+        for id in 1..=200_u32 {
+            let player = Felt::from(id);
+            let models = if id % 2 == 0 {
+                vec![]
+            } else {
+                vec![mock::position_struct(player, id, 1)]
+            };
+            app.world_mut()
+                .send_event(mock::entity_updated(player, models));
+        }
+        // Long enough for the despawn animations to complete.
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(app.world().resource::<EntityIndex>().len(), 100);
+        assert_index_matches_players(&mut app);
+
+        app.world_mut().send_event(ResyncRequest);
+        app.update();
+        assert!(app.world().resource::<EntityIndex>().is_empty());
+        assert_index_matches_players(&mut app);
+    }
+
+    #[test]
+    fn position_burst_is_coalesced_per_entity() {
+        let mut app = mock::headless_app();
+        // The whole burst in a single frame.
+        app.insert_resource(MaxUpdatesPerFrame(usize::MAX));
+        app.update();
+
+        for update in mock::position_burst(500, 4) {
+            app.world_mut().send_event(update);
+        }
+        app.update();
+
+        let events = app.world().resource::<Events<PositionUpdatedEvent>>();
+        assert_eq!(events.get_cursor().read(events).count(), 500);
+        let metrics = app.world().resource::<DojoMetrics>();
+        assert_eq!(metrics.entity_updates_received, 2000);
+        assert_eq!(metrics.updates_coalesced, 1500);
+    }
+
+    #[test]
+    fn models_of_a_player_share_its_entity() {
+        let mut app = mock::headless_app();
+        app.update();
+
+        // Moves first, before the position spawns the cube.
+        let player = Felt::from(7_u64);
+        app.world_mut().send_event(mock::entity_updated(
+            player,
+            vec![mock::moves_struct(player, 99)],
+        ));
+        app.update();
+        app.world_mut().send_event(mock::entity_updated(
+            player,
+            vec![mock::position_struct(player, 3, 4)],
+        ));
+        app.update();
+
+        let entity = app.world().resource::<EntityIndex>().get(player);
+        let mut query = app.world_mut().query::<(Entity, &Position, &Moves)>();
+        let players: Vec<_> = query
+            .iter(app.world())
+            .map(|(entity, position, moves)| (entity, position.as_ivec2(), moves.remaining))
+            .collect();
+        assert_eq!(players, vec![(entity.unwrap(), IVec2::new(3, 4), 99)]);
     }
 }
@@ -0,0 +1,100 @@
+//! Floating labels showing which address each player cube belongs to.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use super::{
+    felt::FeltExt,
+    intro::{PendingSpawn, Player},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShowPlayerLabels>();
+    app.add_systems(
+        PostUpdate,
+        (
+            spawn_player_labels,
+            despawn_orphan_labels,
+            update_player_labels.after(TransformSystem::TransformPropagate),
+        ),
+    );
+}
+
+/// Whether player labels are shown. Can be toggled at runtime.
+#[derive(Resource, Debug)]
+pub struct ShowPlayerLabels(pub bool);
+
+impl Default for ShowPlayerLabels {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// A UI label tracking a player cube.
+#[derive(Component, Debug)]
+pub struct PlayerLabel {
+    pub player: Entity,
+}
+
+/// World-space offset of the label above the cube.
+const LABEL_OFFSET: Vec3 = Vec3::new(0.0, 0.6, 0.0);
+
+fn spawn_player_labels(mut commands: Commands, players: Query<(Entity, &Player), Added<Player>>) {
+    for (entity, player) in &players {
+        commands.spawn((
+            Name::new("Player Label"),
+            PlayerLabel { player: entity },
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            Text(player.id.short().to_string()),
+            TextFont::from_font_size(14.0),
+            TextColor(Color::WHITE),
+            // Hidden until positioned on its first frame.
+            Visibility::Hidden,
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+/// Labels outlive their player cube, so they are cleaned up once it's gone.
+fn despawn_orphan_labels(
+    mut commands: Commands,
+    labels: Query<(Entity, &PlayerLabel)>,
+    players: Query<(), With<Player>>,
+) {
+    for (entity, label) in &labels {
+        if !players.contains(label.player) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Projects each player cube to the screen and places its label above it.
+fn update_player_labels(
+    show_labels: Res<ShowPlayerLabels>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    players: Query<&GlobalTransform, (With<Player>, Without<PendingSpawn>)>,
+    mut labels: Query<(&PlayerLabel, &mut Node, &mut Visibility)>,
+) {
+    let Ok((camera, camera_transform)) = camera.single() else {
+        return;
+    };
+
+    for (label, mut node, mut visibility) in &mut labels {
+        let viewport_position = players.get(label.player).ok().and_then(|transform| {
+            camera
+                .world_to_viewport(camera_transform, transform.translation() + LABEL_OFFSET)
+                .ok()
+        });
+
+        match viewport_position {
+            Some(position) if show_labels.0 => {
+                node.left = Px(position.x);
+                node.top = Px(position.y);
+                *visibility = Visibility::Inherited;
+            }
+            _ => *visibility = Visibility::Hidden,
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Counters describing the activity of the Dojo integration.
+
+use bevy::prelude::*;
+use serde::Serialize;
+use std::time::Duration;
+
+use super::{
+    DojoSystemState,
+    intro::PositionUpdatedEvent,
+    tx::{TransactionTracker, TxStatus, TxStatusChanged},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<DojoMetrics>();
+    app.init_resource::<AwaitedConfirmations>();
+    app.add_systems(Update, record_confirmation_latency);
+}
+
+/// Running totals since startup, updated by the Dojo systems.
+#[derive(Resource, Debug, Default)]
+pub struct DojoMetrics {
+    /// Transactions submitted to the [`super::tx::TransactionTracker`].
+    pub txs_queued: u64,
+    /// Entity updates received from Torii, deletions included.
+    pub entity_updates_received: u64,
+    /// Received updates merged into a pending one for the same entity, see
+    /// [`super::throttle::DeferredUpdates`].
+    pub updates_coalesced: u64,
+    /// `Position` models successfully parsed.
+    pub positions_parsed: u64,
+    /// Models that failed to parse.
+    pub parse_errors: u64,
+    /// Connections re-established after the initial one.
+    pub reconnects: u64,
+    /// Time from the submission of a spawn or move to the next position of the local player
+    /// Torii sends once the transaction is confirmed.
+    pub confirmation_latency: LatencyHistogram,
+}
+
+/// A copy of the [`DojoMetrics`] counters at a point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DojoMetricsSnapshot {
+    pub txs_queued: u64,
+    pub entity_updates_received: u64,
+    pub updates_coalesced: u64,
+    pub positions_parsed: u64,
+    pub parse_errors: u64,
+    pub reconnects: u64,
+    pub confirmations: u64,
+    pub confirmation_p50_ms: Option<u128>,
+    pub confirmation_p95_ms: Option<u128>,
+}
+
+impl DojoMetrics {
+    pub fn metrics_snapshot(&self) -> DojoMetricsSnapshot {
+        let percentile_ms = |percentile| {
+            self.confirmation_latency
+                .percentile(percentile)
+                .map(|latency| latency.as_millis())
+        };
+        DojoMetricsSnapshot {
+            txs_queued: self.txs_queued,
+            entity_updates_received: self.entity_updates_received,
+            updates_coalesced: self.updates_coalesced,
+            positions_parsed: self.positions_parsed,
+            parse_errors: self.parse_errors,
+            reconnects: self.reconnects,
+            confirmations: self.confirmation_latency.count(),
+            confirmation_p50_ms: percentile_ms(0.5),
+            confirmation_p95_ms: percentile_ms(0.95),
+        }
+    }
+}
+
+/// Upper bounds of the [`LatencyHistogram`] buckets, an extra bucket holding the slower ones.
+pub const LATENCY_BUCKETS: [Duration; 10] = [
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+    Duration::from_millis(300),
+    Duration::from_millis(500),
+    Duration::from_millis(750),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+];
+
+/// Latencies counted in the [`LATENCY_BUCKETS`].
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    counts: [u64; LATENCY_BUCKETS.len() + 1],
+    total: u64,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.total += 1;
+        self.max = self.max.max(latency);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// Each bucket with its upper bound, `None` for the one beyond the last bound.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        LATENCY_BUCKETS
+            .iter()
+            .copied()
+            .map(Some)
+            .chain([None])
+            .zip(self.counts.iter().copied())
+    }
+
+    /// The latency under which `percentile` of the recorded ones are, e.g. `0.95`, rounded up
+    /// to the bound of its bucket but never above the slowest one. `None` until one is recorded.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.total == 0 {
+            return None;
+        }
+        let rank = ((percentile.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bound, count) in self.buckets() {
+            seen += count;
+            if seen >= rank {
+                return Some(bound.map_or(self.max, |bound| bound.min(self.max)));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+/// Transactions given up on after this long without a position, e.g. moves into a wall,
+/// which the contract accepts without moving the player.
+const MAX_CONFIRMATION_WAIT: Duration = Duration::from_secs(30);
+
+/// Confirmed player actions whose position Torii didn't send yet, with their submission time.
+#[derive(Resource, Debug, Default)]
+struct AwaitedConfirmations(Vec<Duration>);
+
+/// A position of the local player reflects every player action confirmed before it, since
+/// Torii may send a single update for several moves.
+fn record_confirmation_latency(
+    time: Res<Time>,
+    dojo_state: Res<DojoSystemState>,
+    tx_tracker: Res<TransactionTracker>,
+    mut metrics: ResMut<DojoMetrics>,
+    mut awaited: ResMut<AwaitedConfirmations>,
+    mut ev_tx_status: EventReader<TxStatusChanged>,
+    mut ev_position_updated: EventReader<PositionUpdatedEvent>,
+) {
+    let config = &dojo_state.config;
+    for TxStatusChanged { id, status } in ev_tx_status.read() {
+        let Some(tracked) = tx_tracker.get(*id) else {
+            continue;
+        };
+        let is_player_action = tracked.calls.iter().any(|call| {
+            call.to == config.action_address
+                && (call.selector == config.spawn_selector || call.selector == config.move_selector)
+        });
+        if *status == TxStatus::Confirmed && is_player_action {
+            awaited.0.push(tracked.submitted_at);
+        }
+    }
+
+    let now = time.elapsed();
+    let local_updates = ev_position_updated
+        .read()
+        .filter(|ev| Some(ev.position.player) == dojo_state.local_player_address)
+        .count();
+    if local_updates > 0 {
+        for submitted_at in awaited.0.drain(..) {
+            metrics
+                .confirmation_latency
+                .record(now.saturating_sub(submitted_at));
+        }
+    } else {
+        awaited
+            .0
+            .retain(|submitted_at| now.saturating_sub(*submitted_at) <= MAX_CONFIRMATION_WAIT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(latencies: &[(u64, usize)]) -> LatencyHistogram {
+        let mut histogram = LatencyHistogram::default();
+        for &(millis, times) in latencies {
+            for _ in 0..times {
+                histogram.record(Duration::from_millis(millis));
+            }
+        }
+        histogram
+    }
+
+    #[test]
+    fn percentiles_need_a_latency() {
+        assert_eq!(LatencyHistogram::default().percentile(0.5), None);
+    }
+
+    #[test]
+    fn a_single_latency_is_every_percentile() {
+        let histogram = histogram(&[(120, 1)]);
+
+        for percentile in [0.0, 0.5, 0.95, 1.0] {
+            assert_eq!(
+                histogram.percentile(percentile),
+                Some(Duration::from_millis(120))
+            );
+        }
+    }
+
+    #[test]
+    fn percentiles_are_rounded_up_to_their_bucket() {
+        let histogram = histogram(&[(40, 10), (150, 9), (900, 1)]);
+
+        assert_eq!(histogram.percentile(0.5), Some(Duration::from_millis(50)));
+        assert_eq!(histogram.percentile(0.95), Some(Duration::from_millis(200)));
+        // Never above the slowest latency.
+        assert_eq!(histogram.percentile(1.0), Some(Duration::from_millis(900)));
+    }
+
+    #[test]
+    fn latencies_beyond_the_last_bucket_report_the_slowest() {
+        let histogram = histogram(&[(40, 1), (12_000, 1), (30_000, 1)]);
+
+        assert_eq!(histogram.percentile(0.95), Some(Duration::from_secs(30)));
+        assert_eq!(histogram.buckets().last(), Some((None, 2)));
+    }
+}
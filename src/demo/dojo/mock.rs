@@ -0,0 +1,175 @@
+//! Test doubles to drive the Dojo systems without Katana or Torii.
+//!
+//! Only compiled for the tests and with the `mock_dojo` feature. Typical use in a headless test:
+//!
+//! ```ignore
+//! let mut app = headless_app();
+//! let mock = install_mock_dojo(&mut app);
+//! app.world_mut().send_event(entity_updated(
+//!     Felt::ONE,
+//!     vec![position_struct(player, 3, 4)],
+//! ));
+//! app.update();
+//! assert!(mock.queued_calls().is_empty());
+//! ```
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::{DojoEntityUpdated, DojoResource, TokioRuntime};
+use dojo_types::{
+    primitive::Primitive,
+    schema::{Enum, EnumOption, Member, Struct, Ty},
+};
+use starknet::core::types::{Call, Felt};
+use std::sync::{Arc, Mutex};
+
+use super::{
+    DojoInitMode, DojoPlugin,
+    tx::{TransactionTracker, TxBackend, TxContext, TxStatus},
+};
+use crate::constants::dojo::DojoConfig;
+
+/// A [`TxBackend`] recording the calls instead of sending them.
+///
+/// Clones share the same recording, so a test can keep one to assert on.
+#[derive(Clone, Default)]
+pub struct MockDojo {
+    queued_calls: Arc<Mutex<Vec<Vec<Call>>>>,
+    /// Status reported for every recorded transaction. `None` leaves them pending.
+    pub outcome: Option<TxStatus>,
+}
+
+impl MockDojo {
+    /// Every batch of calls submitted so far, in order.
+    pub fn queued_calls(&self) -> Vec<Vec<Call>> {
+        self.queued_calls
+            .lock()
+            .map(|calls| calls.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl TxBackend for MockDojo {
+    fn send(&self, context: TxContext, calls: Vec<Call>) {
+        if let Ok(mut queued_calls) = self.queued_calls.lock() {
+            queued_calls.push(calls);
+        }
+        if let Some(status) = self.outcome.clone() {
+            context.reporter.finish(status);
+        }
+    }
+}
+
+/// An app running the Dojo plugin without a window nor a renderer, on the default
+/// configuration. Nothing connects until [`ConnectNow`](super::ConnectNow).
+pub fn headless_app() -> App {
+    headless_app_with_config(DojoConfig::default())
+}
+
+/// A [`headless_app`] on the given configuration, e.g. to reach the containers of an
+/// end-to-end test.
+pub fn headless_app_with_config(config: DojoConfig) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        bevy::input::InputPlugin,
+    ))
+    // Loaded by the `GizmoPlugin`.
+    .init_asset::<Shader>()
+    .add_plugins(bevy::gizmos::GizmoPlugin)
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_resource::<DojoResource>()
+    .init_resource::<TokioRuntime>()
+    // The plugin of `dojo_bevy_plugin`, sending the Torii events, as in the `AppPlugin`.
+    .add_plugins(dojo_bevy_plugin::DojoPlugin)
+    .add_plugins(
+        DojoPlugin::default()
+            .with_config(config)
+            .with_init_mode(DojoInitMode::Deferred),
+    );
+    app
+}
+
+/// Routes the app's transactions to a new [`MockDojo`] and returns it.
+pub fn install_mock_dojo(app: &mut App) -> MockDojo {
+    let mock = MockDojo::default();
+    app.world_mut()
+        .resource_mut::<TransactionTracker>()
+        .set_backend(mock.clone());
+    mock
+}
+
+/// Builds the event Torii would send for an entity.
+pub fn entity_updated(entity_id: Felt, models: Vec<Struct>) -> DojoEntityUpdated {
+    DojoEntityUpdated { entity_id, models }
+}
+
+/// Builds `updates_per_entity` successive moves for each of `entities` players, interleaved
+/// the way a busy world sends them.
+pub fn position_burst(entities: u32, updates_per_entity: u32) -> Vec<DojoEntityUpdated> {
+    (0..updates_per_entity)
+        .flat_map(|step| {
+            (0..entities).map(move |entity| {
+                let player = Felt::from(entity + 1);
+                entity_updated(player, vec![position_struct(player, step, entity)])
+            })
+        })
+        .collect()
+}
+
+fn member(name: &str, primitive: Primitive, key: bool) -> Member {
+    Member {
+        name: name.to_string(),
+        ty: Ty::Primitive(primitive),
+        key,
+    }
+}
+
+/// Builds a `di-Position` model as received from Torii.
+pub fn position_struct(player: Felt, x: u32, y: u32) -> Struct {
+    Struct {
+        name: "di-Position".to_string(),
+        children: vec![
+            member("player", Primitive::ContractAddress(Some(player)), true),
+            member("x", Primitive::U32(Some(x)), false),
+            member("y", Primitive::U32(Some(y)), false),
+        ],
+    }
+}
+
+/// Builds a `di-Moves` model as received from Torii, without a last direction.
+pub fn moves_struct(player: Felt, remaining: u8) -> Struct {
+    let unit = |name: &str| EnumOption {
+        name: name.to_string(),
+        ty: Ty::Tuple(vec![]),
+    };
+    let direction = Enum {
+        name: "Direction".to_string(),
+        option: None,
+        options: ["Left", "Right", "Up", "Down"].map(unit).to_vec(),
+    };
+    let last_direction = Enum {
+        name: "Option<Direction>".to_string(),
+        option: Some(1),
+        options: vec![
+            EnumOption {
+                name: "Some".to_string(),
+                ty: Ty::Enum(direction),
+            },
+            unit("None"),
+        ],
+    };
+    Struct {
+        name: "di-Moves".to_string(),
+        children: vec![
+            member("player", Primitive::ContractAddress(Some(player)), true),
+            member("remaining", Primitive::U8(Some(remaining)), false),
+            Member {
+                name: "last_direction".to_string(),
+                ty: Ty::Enum(last_direction),
+                key: false,
+            },
+        ],
+    }
+}
@@ -1,88 +1,838 @@
-use crate::constants::dojo::DojoConfig;
-use bevy::prelude::*;
-use dojo_bevy_plugin::{DojoResource, TokioRuntime};
+// Unused utilities may trigger this lints undesirably.
+#![allow(dead_code)]
 
+use crate::constants::dojo::{DevAccount, DojoConfig};
+use bevy::{platform::time::Instant, prelude::*};
+use dojo_bevy_plugin::{DojoEntityUpdated, DojoInitializedEvent, DojoResource, TokioRuntime};
+use error::{DojoError, DojoErrorEvent};
+use starknet::{accounts::Account, core::types::Felt, signers::SigningKey};
+use std::{env, path::PathBuf, time::Duration};
+use tx::{TransactionTracker, TxStatusChanged};
+
+pub mod actions;
+pub mod animation;
+pub mod appearance;
+pub mod audio;
+pub mod camera;
+pub mod chain;
+pub mod coordinates;
+pub mod dev_accounts;
+pub mod dump;
+pub mod error;
+pub mod eviction;
+pub mod external_account;
+pub mod felt;
+pub mod fetch;
+mod gamepad;
+pub mod ghost;
+pub mod grid;
+pub mod health;
+mod help;
+mod hud;
+#[cfg(feature = "dev-inspector")]
+mod inspector;
 pub mod intro;
+pub mod labels;
+pub mod metrics;
+#[cfg(any(test, feature = "mock_dojo"))]
+pub mod mock;
+pub mod models;
+pub mod pause;
+pub mod persistence;
+pub mod prediction;
+pub mod query;
+pub mod reconnect;
+pub mod registry;
+pub mod replay;
+pub mod rollback;
+pub mod scene;
+pub mod stale;
+pub mod subscription;
+pub mod sync;
+pub mod task;
+pub mod throttle;
+pub mod touch;
+pub mod trail;
+pub mod tx;
+
+/// Target of every Dojo log and span, so they can be filtered with `RUST_LOG=bevy_dojo=debug`.
+pub const LOG_TARGET: &str = "bevy_dojo";
 
 /// Resource to track overall Dojo system state
 #[derive(Resource, Debug, Default)]
 pub struct DojoSystemState {
     pub torii_connected: bool,
+    /// Set once [`AccountConnectedEvent`] fired, not when the connection is merely initiated.
     pub account_connected: bool,
+    /// Contract address of the connected account, once the plugin has finished connecting it.
+    pub local_player_address: Option<Felt>,
     pub last_error: Option<String>,
     pub config: DojoConfig,
+    /// Round-trip time of the last successful Torii ping, see [`health::ToriiHealthCheck`].
+    pub last_ping: Option<Duration>,
+    pub last_ping_at: Option<Instant>,
+    /// Set when the RPC doesn't serve [`DojoConfig::expected_chain_id`], which blocks transactions.
+    pub wrong_chain: bool,
+    /// Most recent block an update came from, see [`intro::PositionUpdatedEvent::block`].
+    pub latest_block: Option<u64>,
+    /// Set while the account connection initiated at startup is in progress.
+    pub account_connecting: bool,
+}
+
+impl DojoSystemState {
+    /// Whether transactions can be sent: both connections are up, on the expected chain.
+    pub fn is_ready(&self) -> bool {
+        self.torii_connected && self.account_connected && !self.wrong_chain
+    }
+}
+
+/// Run condition for the systems that need Dojo fully connected, see [`DojoSystemState::is_ready`].
+pub fn dojo_ready(dojo_state: Res<DojoSystemState>) -> bool {
+    dojo_state.is_ready()
+}
+
+/// Emitted once the plugin stored a usable account.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AccountConnectedEvent {
+    pub address: Felt,
 }
 
+/// Emitted when the account connection didn't complete.
+#[derive(Event, Debug, Clone)]
+pub struct AccountConnectionFailedEvent(pub String);
+
+/// Requests the Dojo connections to be torn down, see [`disconnect_dojo`].
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct DisconnectRequest;
+
+/// Emitted once [`disconnect_dojo`] dropped the connections.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DojoDisconnected;
+
+/// An account connection that was initiated but not confirmed yet.
+#[derive(Resource, Debug)]
+pub struct PendingAccountConnection {
+    /// Elapsed app time when the connection was initiated.
+    pub started_at: Duration,
+    /// How long to wait for the plugin before giving up.
+    pub timeout: Duration,
+}
+
+/// The Dojo integration of the demo, configured when building the `App`:
+///
+/// ```ignore
+/// app.add_plugins(
+///     DojoPlugin::default()
+///         .dev_account(2)
+///         .auto_subscribe(&[Position::NAME]),
+/// );
+/// ```
+///
+/// Unlike `dojo_bevy_plugin::DojoPlugin`, which provides the connections, it handles the
+/// world of this starter. Anything left unset is read from `dojo.toml` and the environment.
+#[derive(Debug, Clone, Default)]
+pub struct DojoPlugin {
+    config: Option<DojoConfig>,
+    key_bindings: Option<intro::KeyBindings>,
+    dev_account: Option<usize>,
+    auto_subscribe: Option<Vec<String>>,
+    init_mode: DojoInitMode,
+}
+
+impl DojoPlugin {
+    /// Uses `config` instead of reading `dojo.toml` and the environment.
+    pub fn with_config(mut self, config: DojoConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub fn with_keybindings(mut self, key_bindings: intro::KeyBindings) -> Self {
+        self.key_bindings = Some(key_bindings);
+        self
+    }
+
+    /// Connects the Katana account predeployed at `index`, whatever the configuration says.
+    pub fn dev_account(mut self, index: usize) -> Self {
+        self.dev_account = Some(index);
+        self
+    }
+
+    /// Subscribes to `models` as soon as Torii is connected, instead of waiting for
+    /// [`KeyBindings::subscribe`]. Empty subscribes to every registered model.
+    ///
+    /// [`KeyBindings::subscribe`]: intro::KeyBindings::subscribe
+    pub fn auto_subscribe(mut self, models: &[&str]) -> Self {
+        self.auto_subscribe = Some(models.iter().map(|model| model.to_string()).collect());
+        self
+    }
+
+    pub fn with_init_mode(mut self, init_mode: DojoInitMode) -> Self {
+        self.init_mode = init_mode;
+        self
+    }
+}
+
+/// When the Dojo connections are initiated.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DojoInitMode {
+    /// At startup, right after the configuration is loaded.
+    #[default]
+    Async,
+    /// Only on [`ConnectNow`], e.g. once a test set up the mock or the replay, or when the
+    /// player pressed a "connect" button.
+    Deferred,
+}
+
+/// Initiates the Dojo connections in [`DojoInitMode::Deferred`]. Ignored once connected.
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ConnectNow;
+
+/// What [`DojoPlugin`] overrides in the configuration, applied by `setup_dojo_config`.
+#[derive(Resource, Debug, Default)]
+struct ConfigOverrides {
+    config: Option<DojoConfig>,
+    dev_account: Option<usize>,
+}
+
+/// Default configuration, see [`DojoPlugin`].
 pub fn plugin(app: &mut App) {
+    app.add_plugins(DojoPlugin::default());
+}
+
+impl Plugin for DojoPlugin {
+    fn build(&self, app: &mut App) {
+        build_dojo(app);
+
+        app.insert_resource(ConfigOverrides {
+            config: self.config.clone(),
+            dev_account: self.dev_account,
+        })
+        .insert_resource(self.init_mode);
+        if let Some(key_bindings) = &self.key_bindings {
+            app.insert_resource(key_bindings.clone());
+        }
+        if let Some(models) = &self.auto_subscribe {
+            app.insert_resource(subscription::SubscribedModels(models.clone()))
+                .insert_resource(subscription::AutoSubscribe);
+        }
+    }
+}
+
+fn build_dojo(app: &mut App) {
     app.init_resource::<DojoSystemState>()
-        .add_systems(Startup, (setup_dojo_config, handle_dojo_setup).chain())
+        .init_resource::<DojoInitMode>()
+        .add_event::<ConnectNow>()
+        .add_event::<AccountConnectedEvent>()
+        .add_event::<AccountConnectionFailedEvent>()
+        .add_event::<DisconnectRequest>()
+        .add_event::<DojoDisconnected>()
+        .add_event::<SwitchWorld>()
+        .init_resource::<StatusLogConfig>()
+        .add_systems(
+            Startup,
+            (
+                setup_dojo_config,
+                replay::load_replay,
+                // A replay stands in for Torii, so nothing is connected.
+                handle_dojo_setup
+                    .run_if(|init_mode: Res<DojoInitMode>| *init_mode == DojoInitMode::Async)
+                    .run_if(not(resource_exists::<replay::ToriiReplay>))
+                    .run_if(resource_exists::<TokioRuntime>),
+                warn_missing_tokio_runtime.run_if(not(resource_exists::<TokioRuntime>)),
+            )
+                .chain(),
+        )
         .add_systems(
             Update,
-            log_dojo_status.run_if(resource_changed::<DojoSystemState>),
+            (
+                log_dojo_status,
+                connect_on_request
+                    .run_if(on_event::<ConnectNow>)
+                    .run_if(not(resource_exists::<replay::ToriiReplay>))
+                    .run_if(resource_exists::<TokioRuntime>),
+                watch_account_connection.run_if(resource_exists::<PendingAccountConnection>),
+                (apply_account_connection, track_account_connecting)
+                    .chain()
+                    .after(watch_account_connection),
+                switch_world
+                    .run_if(on_event::<SwitchWorld>)
+                    .run_if(resource_exists::<TokioRuntime>),
+            ),
+        )
+        // `Last` so the connections are still closed on the frame the app exits.
+        .add_systems(
+            Last,
+            disconnect_dojo.run_if(on_event::<DisconnectRequest>.or(on_event::<AppExit>)),
         )
-        .add_plugins(intro::plugin);
+        .add_plugins((
+            intro::plugin,
+            actions::plugin,
+            chain::plugin,
+            dev_accounts::plugin,
+            dump::plugin,
+            error::plugin,
+            eviction::plugin,
+            external_account::plugin,
+            health::plugin,
+            metrics::plugin,
+            pause::plugin,
+            reconnect::plugin,
+            registry::plugin,
+            tx::plugin,
+        ))
+        // Fetching the world.
+        .add_plugins((
+            fetch::plugin,
+            persistence::plugin,
+            replay::plugin,
+            stale::plugin,
+            subscription::plugin,
+            sync::plugin,
+            throttle::plugin,
+        ))
+        // Input.
+        .add_plugins((
+            gamepad::plugin,
+            prediction::plugin,
+            rollback::plugin,
+            touch::plugin,
+        ))
+        // Presentation.
+        .add_plugins((
+            animation::plugin,
+            appearance::plugin,
+            audio::plugin,
+            camera::plugin,
+            coordinates::plugin,
+            ghost::plugin,
+            grid::plugin,
+            help::plugin,
+            hud::plugin,
+            #[cfg(feature = "dev-inspector")]
+            inspector::plugin,
+            labels::plugin,
+            scene::plugin,
+            trail::plugin,
+        ));
 }
 
-fn setup_dojo_config(mut dojo_state: ResMut<DojoSystemState>) {
-    dojo_state.config = DojoConfig::default();
-    info!("Dojo configuration loaded: {:?}", dojo_state.config);
+fn setup_dojo_config(
+    overrides: Res<ConfigOverrides>,
+    mut dojo_state: ResMut<DojoSystemState>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    // `DOJO_CONFIG` points to a TOML file; `dojo.toml` in the working directory is used otherwise.
+    let config_path = env::var("DOJO_CONFIG")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("dojo.toml"));
+
+    dojo_state.config = if let Some(config) = overrides.config.clone() {
+        info!(target: LOG_TARGET, "Dojo configuration provided by the plugin");
+        config
+    } else if config_path.exists() {
+        match DojoConfig::from_toml_path(&config_path) {
+            Ok(config) => {
+                info!(target: LOG_TARGET, "Dojo configuration read from {}", config_path.display());
+                config
+            }
+            Err(error) => {
+                ev_error.write(DojoError::from(error).into());
+                DojoConfig::default()
+            }
+        }
+    } else {
+        match DojoConfig::from_env() {
+            Ok(config) => config,
+            Err(error) => {
+                ev_error.write(DojoError::from(error).into());
+                DojoConfig::default()
+            }
+        }
+    };
+    if let Some(index) = overrides.dev_account {
+        dojo_state.config.use_dev_account = true;
+        dojo_state.config.dev_account = DevAccount::Index(index);
+    }
+    info!(target: LOG_TARGET, "Dojo configuration loaded: {:?}", dojo_state.config);
+
+    if let Err(errors) = dojo_state.config.validate() {
+        ev_error.write(DojoError::ConfigInvalid(errors).into());
+    }
 
-    // Warn about development account usage
-    if dojo_state.config.use_dev_account {
-        warn!("Using development account - NOT SUITABLE FOR PRODUCTION");
-        warn!("Set USE_DEV_ACCOUNT=false for production deployment");
+    let network = dojo_state.config.network();
+    if !network.is_local() {
+        info!(target: LOG_TARGET, %network, "Connecting to a remote network");
+        if dojo_state.config.use_dev_account {
+            warn!(
+                target: LOG_TARGET,
+                %network,
+                "Predeployed accounts only exist on a local Katana, ignoring USE_DEV_ACCOUNT"
+            );
+        }
+    } else if dojo_state.config.use_dev_account {
+        // Warn about development account usage
+        warn!(target: LOG_TARGET, "Using development account - NOT SUITABLE FOR PRODUCTION");
+        warn!(target: LOG_TARGET, "Set USE_DEV_ACCOUNT=false for production deployment");
     }
 }
 
 fn handle_dojo_setup(
+    mut commands: Commands,
+    time: Res<Time>,
+    tokio: Res<TokioRuntime>,
+    mut dojo: ResMut<DojoResource>,
+    mut dojo_state: ResMut<DojoSystemState>,
+) {
+    connect_dojo(
+        &mut commands,
+        time.elapsed(),
+        &tokio,
+        &mut dojo,
+        &mut dojo_state,
+    );
+}
+
+fn connect_on_request(
+    mut commands: Commands,
+    time: Res<Time>,
     tokio: Res<TokioRuntime>,
     mut dojo: ResMut<DojoResource>,
     mut dojo_state: ResMut<DojoSystemState>,
+) {
+    if dojo_state.torii_connected {
+        debug!(target: LOG_TARGET, "Already connected, ignoring ConnectNow");
+        return;
+    }
+    connect_dojo(
+        &mut commands,
+        time.elapsed(),
+        &tokio,
+        &mut dojo,
+        &mut dojo_state,
+    );
+}
+
+/// Systems needing the runtime are skipped without it, rather than panicking every frame.
+fn warn_missing_tokio_runtime() {
+    error!(
+        target: LOG_TARGET,
+        "TokioRuntime is missing: add the Dojo plugin before this one. Dojo stays disconnected"
+    );
+}
+
+/// Initiates the Torii and account connections described by `dojo_state.config`.
+fn connect_dojo(
+    commands: &mut Commands,
+    now: Duration,
+    tokio: &TokioRuntime,
+    dojo: &mut DojoResource,
+    dojo_state: &mut DojoSystemState,
 ) {
     let config = dojo_state.config.clone();
 
-    info!("Attempting to connect to Dojo services...");
+    if config.validate().is_err() {
+        warn!(target: LOG_TARGET, "Skipping Dojo connection because the configuration is invalid");
+        return;
+    }
+
+    info!(target: LOG_TARGET, "Attempting to connect to Dojo services...");
 
-    info!("Connecting to Torii at {}...", config.torii_url);
-    dojo.connect_torii(&tokio, config.torii_url.clone(), config.world_address);
-    info!("Torii connection initiated successfully");
-    dojo_state.torii_connected = true;
+    {
+        let _span = info_span!(
+            target: LOG_TARGET,
+            "torii_connect",
+            url = %config.torii_url,
+            world = ?config.world_address
+        )
+        .entered();
+        info!(target: LOG_TARGET, "Connecting to Torii");
+        dojo.connect_torii(tokio, config.torii_url.clone(), config.world_address);
+        info!(target: LOG_TARGET, "Torii connection initiated successfully");
+        dojo_state.torii_connected = true;
+    }
+
+    if !connect_account(commands, now, tokio, dojo, &config) {
+        if config.network().is_local() {
+            info!(
+                target: LOG_TARGET,
+                "Development account disabled - set ACCOUNT_ADDRESS and ACCOUNT_PRIVATE_KEY, \
+                 or connect an account manually"
+            );
+        } else {
+            warn!(
+                target: LOG_TARGET,
+                network = %config.network(),
+                "No account for the remote network - set ACCOUNT_ADDRESS and \
+                 ACCOUNT_PRIVATE_KEY, or connect an account manually. Only watching the world"
+            );
+        }
+    }
 
-    if config.use_dev_account {
-        info!(
-            "Connecting to Katana account #{} at {}...",
-            config.dev_account_index, config.katana_url
+    if dojo_state.torii_connected {
+        info!(target: LOG_TARGET, "Dojo blockchain integration initialized successfully");
+        info!(target: LOG_TARGET, "Press 'Space' to spawn");
+    } else {
+        warn!(
+            target: LOG_TARGET,
+            "Dojo integration has connection issues - game may have limited functionality"
         );
-        dojo.connect_predeployed_account(
-            &tokio,
-            config.katana_url.clone(),
-            config.dev_account_index as usize,
+    }
+}
+
+/// Initiates the account connection described by `config`, the dev account or an external
+/// one, and returns whether there was one to connect. The dev account is only connected on a
+/// local network.
+fn connect_account(
+    commands: &mut Commands,
+    now: Duration,
+    tokio: &TokioRuntime,
+    dojo: &mut DojoResource,
+    config: &DojoConfig,
+) -> bool {
+    if config.use_dev_account && config.network().is_local() {
+        let _span = info_span!(
+            target: LOG_TARGET,
+            "account_connect",
+            url = %config.katana_url,
+            account = %config.dev_account
+        )
+        .entered();
+        // An address is only connected once Katana listed its predeployed accounts.
+        dev_accounts::start_dev_account_lookup(commands, tokio, config);
+        if let DevAccount::Index(index) = config.dev_account {
+            info!(target: LOG_TARGET, "Connecting to Katana account");
+            dojo.connect_predeployed_account(tokio, config.katana_url.clone(), index);
+            info!(target: LOG_TARGET, "Katana account connection initiated successfully");
+        }
+        chain::start_chain_id_check(commands, tokio, config);
+        commands.insert_resource(PendingAccountConnection {
+            started_at: now,
+            timeout: Duration::from_secs(10),
+        });
+        true
+    } else if let (Some(address), Some(private_key)) =
+        (config.account_address, config.account_private_key)
+    {
+        let _span = info_span!(
+            target: LOG_TARGET,
+            "account_connect",
+            url = %config.katana_url,
+            account = ?address
+        )
+        .entered();
+        external_account::connect_external_account(
+            commands,
+            tokio,
+            &config.katana_url,
+            address,
+            SigningKey::from_secret_scalar(private_key.expose()),
         );
-        info!("Katana account connection initiated successfully");
-        dojo_state.account_connected = true;
+        chain::start_chain_id_check(commands, tokio, config);
+        commands.insert_resource(PendingAccountConnection {
+            started_at: now,
+            timeout: Duration::from_secs(10),
+        });
+        true
     } else {
-        info!("Development account disabled - manual account connection required");
+        false
     }
+}
 
-    if dojo_state.torii_connected && (dojo_state.account_connected || !config.use_dev_account) {
-        info!("Dojo blockchain integration initialized successfully");
-        info!("🎮 Press 'S' to respawn duck!");
-    } else {
-        warn!("Dojo integration has connection issues - game may have limited functionality");
+/// Mirrors whether [`PendingAccountConnection`] exists, for the status display.
+fn track_account_connecting(
+    pending: Option<Res<PendingAccountConnection>>,
+    mut dojo_state: ResMut<DojoSystemState>,
+) {
+    // Only written on change, as `log_dojo_status` runs whenever the state changes.
+    let connecting = pending.is_some();
+    if dojo_state.account_connecting != connecting {
+        dojo_state.account_connecting = connecting;
     }
 }
 
-fn log_dojo_status(dojo_state: Res<DojoSystemState>) {
-    if let Some(error) = &dojo_state.last_error {
-        error!("❌ Dojo Error: {}", error);
+/// The account connects asynchronously, so it's only usable once the plugin stores it.
+fn watch_account_connection(
+    mut commands: Commands,
+    time: Res<Time>,
+    dojo: Res<DojoResource>,
+    pending: Res<PendingAccountConnection>,
+    mut ev_connected: EventWriter<AccountConnectedEvent>,
+    mut ev_failed: EventWriter<AccountConnectionFailedEvent>,
+) {
+    if let Some(account) = &dojo.sn.account {
+        ev_connected.write(AccountConnectedEvent {
+            address: account.address(),
+        });
+        commands.remove_resource::<PendingAccountConnection>();
+    } else if time.elapsed().saturating_sub(pending.started_at) >= pending.timeout {
+        ev_failed.write(AccountConnectionFailedEvent(format!(
+            "account connection timed out after {:?}",
+            pending.timeout
+        )));
+        commands.remove_resource::<PendingAccountConnection>();
     }
+}
+
+fn apply_account_connection(
+    mut dojo_state: ResMut<DojoSystemState>,
+    mut ev_connected: EventReader<AccountConnectedEvent>,
+    mut ev_failed: EventReader<AccountConnectionFailedEvent>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    for event in ev_connected.read() {
+        info!(target: LOG_TARGET, "Local player address: {:#x}", event.address);
+        dojo_state.account_connected = !dojo_state.wrong_chain;
+        dojo_state.local_player_address = Some(event.address);
+    }
+    for AccountConnectionFailedEvent(reason) in ev_failed.read() {
+        dojo_state.account_connected = false;
+        ev_error.write(DojoError::ConnectionFailed(format!("Katana account: {reason}")).into());
+    }
+}
 
-    let connection_status = match (dojo_state.torii_connected, dojo_state.account_connected) {
-        (true, true) => "✅ Fully Connected - Ready for dojo interactions",
-        (true, false) => "⚠️ Partially Connected - Torii only (manual account required)",
-        (false, true) => "⚠️ Partially Connected - Account only (Torii connection failed)",
-        (false, false) => "❌ Disconnected - No dojo functionality available",
+/// Closes the Torii and account connections and resets [`DojoSystemState`].
+///
+/// The plugin doesn't expose a way to close its clients, so the [`DojoResource`] is replaced by a
+/// fresh one: dropping the clients closes the Torii subscription stream and the account provider.
+/// The transaction tasks spawned on the Tokio runtime are aborted.
+fn disconnect_dojo(
+    mut commands: Commands,
+    mut dojo: ResMut<DojoResource>,
+    mut dojo_state: ResMut<DojoSystemState>,
+    mut tx_tracker: ResMut<TransactionTracker>,
+    mut ev_disconnect: EventReader<DisconnectRequest>,
+    mut ev_disconnected: EventWriter<DojoDisconnected>,
+    mut ev_tx_status: EventWriter<TxStatusChanged>,
+) {
+    ev_disconnect.clear();
+    close_connections(
+        &mut commands,
+        &mut dojo,
+        &mut dojo_state,
+        &mut tx_tracker,
+        &mut ev_tx_status,
+    );
+    ev_disconnected.write(DojoDisconnected);
+}
+
+fn close_connections(
+    commands: &mut Commands,
+    dojo: &mut DojoResource,
+    dojo_state: &mut DojoSystemState,
+    tx_tracker: &mut TransactionTracker,
+    ev_tx_status: &mut EventWriter<TxStatusChanged>,
+) {
+    info!(target: LOG_TARGET, "Disconnecting from Dojo services...");
+
+    for id in tx_tracker.cancel_all() {
+        if let Some(status) = tx_tracker.status(id).cloned() {
+            ev_tx_status.write(TxStatusChanged { id, status });
+        }
+    }
+
+    *dojo = DojoResource::default();
+    commands.remove_resource::<PendingAccountConnection>();
+    commands.remove_resource::<sync::InitialSync>();
+    dojo_state.torii_connected = false;
+    dojo_state.account_connected = false;
+    dojo_state.local_player_address = None;
+    dojo_state.wrong_chain = false;
+    dojo_state.latest_block = None;
+    commands.remove_resource::<chain::ChainIdCheck>();
+    commands.remove_resource::<dev_accounts::DevAccountLookup>();
+    commands.remove_resource::<external_account::ExternalAccountConnection>();
+}
+
+/// Reconnects to another world, replacing [`DojoSystemState::config`].
+///
+/// The players of the previous world are despawned. The initial fetch is re-issued on the
+/// next `DojoInitializedEvent`, like at startup.
+#[derive(Event, Debug, Clone)]
+pub struct SwitchWorld {
+    pub config: DojoConfig,
+}
+
+fn switch_world(
+    mut commands: Commands,
+    time: Res<Time>,
+    tokio: Res<TokioRuntime>,
+    mut dojo: ResMut<DojoResource>,
+    mut dojo_state: ResMut<DojoSystemState>,
+    mut tx_tracker: ResMut<TransactionTracker>,
+    mut metrics: ResMut<metrics::DojoMetrics>,
+    mut entity_tracker: ResMut<intro::EntityTracker>,
+    pending_account: Option<Res<PendingAccountConnection>>,
+    players: Query<Entity, With<intro::Player>>,
+    mut ev_switch: EventReader<SwitchWorld>,
+    (mut ev_initialized, mut ev_entity_updated, mut deferred, mut model_components): (
+        ResMut<Events<DojoInitializedEvent>>,
+        ResMut<Events<DojoEntityUpdated>>,
+        ResMut<throttle::DeferredUpdates>,
+        ResMut<registry::ModelComponents>,
+    ),
+    mut ev_disconnected: EventWriter<DojoDisconnected>,
+    mut ev_tx_status: EventWriter<TxStatusChanged>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    // Only the latest request matters when several arrive in the same frame.
+    let Some(SwitchWorld { config }) = ev_switch.read().last().cloned() else {
+        return;
     };
 
-    info!("🔗 Dojo Status: {}", connection_status);
+    if let Err(errors) = config.validate() {
+        warn!(target: LOG_TARGET, "Not switching world, the configuration is invalid");
+        ev_error.write(DojoError::ConfigInvalid(errors).into());
+        return;
+    }
+
+    // A previous switch may still be connecting: its clients are dropped below,
+    // and its pending account connection replaced.
+    if pending_account.is_some() {
+        info!(target: LOG_TARGET, "Previous connection still in progress, superseding it");
+    }
+    info!(target: LOG_TARGET, "Switching to world {:#x}", config.world_address);
+
+    close_connections(
+        &mut commands,
+        &mut dojo,
+        &mut dojo_state,
+        &mut tx_tracker,
+        &mut ev_tx_status,
+    );
+    ev_disconnected.write(DojoDisconnected);
+
+    // Drop whatever the previous world sent but wasn't processed yet.
+    ev_initialized.clear();
+    ev_entity_updated.clear();
+    deferred.clear();
+    model_components.clear();
+    for entity in &players {
+        commands.entity(entity).despawn();
+    }
+    *entity_tracker = intro::EntityTracker::default();
+
+    dojo_state.config = config;
+    dojo_state.last_error = None;
+    metrics.reconnects += 1;
+    connect_dojo(
+        &mut commands,
+        time.elapsed(),
+        &tokio,
+        &mut dojo,
+        &mut dojo_state,
+    );
+}
+
+/// The overall connection state derived from [`DojoSystemState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Connected,
+    /// Torii is up and the account connection is still in progress.
+    Connecting,
+    ToriiOnly,
+    AccountOnly,
+    Disconnected,
+}
+
+impl ConnectionStatus {
+    pub fn from_state(dojo_state: &DojoSystemState) -> Self {
+        match (dojo_state.torii_connected, dojo_state.account_connected) {
+            (true, true) => Self::Connected,
+            (true, false) if dojo_state.account_connecting => Self::Connecting,
+            (true, false) => Self::ToriiOnly,
+            (false, true) => Self::AccountOnly,
+            (false, false) => Self::Disconnected,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Connected => "Fully Connected - Ready for dojo interactions",
+            Self::Connecting => "Connecting... - Waiting for the account",
+            Self::ToriiOnly => "Partially Connected - Torii only (manual account required)",
+            Self::AccountOnly => "Partially Connected - Account only (Torii connection failed)",
+            Self::Disconnected => "Disconnected - No dojo functionality available",
+        }
+    }
+
+    pub fn emoji(self) -> &'static str {
+        match self {
+            Self::Connected => "✅",
+            Self::Connecting => "⏳",
+            Self::ToriiOnly | Self::AccountOnly => "⚠️",
+            Self::Disconnected => "❌",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Self::Connected => Color::srgb(0.2, 0.8, 0.3),
+            Self::Connecting => Color::srgb(0.4, 0.7, 0.95),
+            Self::ToriiOnly | Self::AccountOnly => Color::srgb(0.95, 0.7, 0.1),
+            Self::Disconnected => Color::srgb(0.9, 0.25, 0.25),
+        }
+    }
+}
+
+/// How often the connection status is logged.
+///
+/// New errors are always logged right away.
+#[derive(Resource, Debug)]
+pub struct StatusLogConfig {
+    pub enabled: bool,
+    /// State changes within this interval of the last status line are folded into the next one.
+    pub min_interval: Duration,
+}
+
+impl Default for StatusLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Default)]
+struct StatusLogState {
+    last_logged_at: Option<Duration>,
+    last_error: Option<String>,
+    /// The state changed since the last status line.
+    unlogged: bool,
+}
+
+fn log_dojo_status(
+    time: Res<Time>,
+    config: Res<StatusLogConfig>,
+    dojo_state: Res<DojoSystemState>,
+    mut log_state: Local<StatusLogState>,
+) {
+    if dojo_state.last_error != log_state.last_error {
+        if let Some(error) = &dojo_state.last_error {
+            error!(target: LOG_TARGET, %error, "Dojo error");
+        }
+        log_state.last_error = dojo_state.last_error.clone();
+    }
+
+    log_state.unlogged |= dojo_state.is_changed();
+    if !config.enabled || !log_state.unlogged {
+        return;
+    }
+    let now = time.elapsed();
+    if log_state
+        .last_logged_at
+        .is_some_and(|logged_at| now.saturating_sub(logged_at) < config.min_interval)
+    {
+        return;
+    }
+    log_state.last_logged_at = Some(now);
+    log_state.unlogged = false;
+
+    let status = ConnectionStatus::from_state(&dojo_state);
+    info!(
+        target: LOG_TARGET,
+        status = status.label(),
+        network = %dojo_state.config.network(),
+        "Dojo status"
+    );
 }
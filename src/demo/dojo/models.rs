@@ -0,0 +1,287 @@
+//! Helpers to read Cairo models received from Torii.
+
+use dojo_types::{
+    primitive::Primitive,
+    schema::{Enum, Struct, Ty},
+};
+use starknet::core::types::Felt;
+use std::{collections::HashMap, fmt};
+
+/// Errors that can occur while mapping a Torii [`Struct`] into a Rust model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelParseError {
+    /// The model doesn't contain the field.
+    MissingField(String),
+    /// The field exists but doesn't hold the expected type.
+    WrongType { field: String, expected: String },
+}
+
+impl fmt::Display for ModelParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingField(field) => write!(f, "missing field '{field}'"),
+            Self::WrongType { field, expected } => {
+                write!(f, "field '{field}' is not a {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModelParseError {}
+
+fn wrong_type(field: &str, expected: &str) -> ModelParseError {
+    ModelParseError::WrongType {
+        field: field.to_string(),
+        expected: expected.to_string(),
+    }
+}
+
+/// Finds a field of a model, or of the structs it nests when the model has no such field, e.g.
+/// `x` in a `Position { player, vec: Vec2 { x, y } }`.
+fn member<'a>(struct_value: &'a Struct, field: &str) -> Result<&'a Ty, ModelParseError> {
+    struct_value
+        .get(field)
+        .or_else(|| {
+            struct_value
+                .children
+                .iter()
+                .filter_map(|child| child.ty.as_struct())
+                .find_map(|nested| member(nested, field).ok())
+        })
+        .ok_or_else(|| ModelParseError::MissingField(field.to_string()))
+}
+
+/// Reads a primitive field from a model.
+pub fn get_primitive<'a>(
+    struct_value: &'a Struct,
+    field: &str,
+) -> Result<&'a Primitive, ModelParseError> {
+    member(struct_value, field)?
+        .as_primitive()
+        .ok_or_else(|| wrong_type(field, "primitive"))
+}
+
+/// Reads a `u32` field from a model.
+pub fn get_u32(struct_value: &Struct, field: &str) -> Result<u32, ModelParseError> {
+    get_primitive(struct_value, field)?
+        .as_u32()
+        .ok_or_else(|| wrong_type(field, "u32"))
+}
+
+/// Reads an integer coordinate from a model, whichever signed or unsigned type the model uses.
+pub fn get_coordinate(struct_value: &Struct, field: &str) -> Result<i32, ModelParseError> {
+    let primitive = get_primitive(struct_value, field)?;
+    let value = primitive
+        .as_i8()
+        .map(i64::from)
+        .or_else(|| primitive.as_i16().map(i64::from))
+        .or_else(|| primitive.as_i32().map(i64::from))
+        .or_else(|| primitive.as_i64())
+        .or_else(|| primitive.as_u8().map(i64::from))
+        .or_else(|| primitive.as_u16().map(i64::from))
+        .or_else(|| primitive.as_u32().map(i64::from))
+        .ok_or_else(|| wrong_type(field, "signed or unsigned integer"))?;
+
+    i32::try_from(value).map_err(|_| wrong_type(field, "coordinate within the i32 range"))
+}
+
+/// Reads a `ContractAddress` field from a model.
+pub fn get_contract_address(struct_value: &Struct, field: &str) -> Result<Felt, ModelParseError> {
+    get_primitive(struct_value, field)?
+        .as_contract_address()
+        .ok_or_else(|| wrong_type(field, "contract address"))
+}
+
+/// Reads a `felt252` field from a model.
+pub fn get_felt(struct_value: &Struct, field: &str) -> Result<Felt, ModelParseError> {
+    get_primitive(struct_value, field)?
+        .as_felt252()
+        .ok_or_else(|| wrong_type(field, "felt252"))
+}
+
+/// Reads a `bool` field from a model.
+pub fn get_bool(struct_value: &Struct, field: &str) -> Result<bool, ModelParseError> {
+    get_primitive(struct_value, field)?
+        .as_bool()
+        .ok_or_else(|| wrong_type(field, "bool"))
+}
+
+/// Reads an `Array<felt252>` or `Span<felt252>` field from a model.
+pub fn get_felt_array(struct_value: &Struct, field: &str) -> Result<Vec<Felt>, ModelParseError> {
+    let expected = "array of felt252";
+    let Ty::Array(items) = member(struct_value, field)? else {
+        return Err(wrong_type(field, expected));
+    };
+    items
+        .iter()
+        .map(|item| {
+            item.as_primitive()
+                .and_then(Primitive::as_felt252)
+                .ok_or_else(|| wrong_type(field, expected))
+        })
+        .collect()
+}
+
+/// Reads a `u8` field from a model.
+pub fn get_u8(struct_value: &Struct, field: &str) -> Result<u8, ModelParseError> {
+    get_primitive(struct_value, field)?
+        .as_u8()
+        .ok_or_else(|| wrong_type(field, "u8"))
+}
+
+/// Reads an enum field from a model.
+pub fn get_enum<'a>(struct_value: &'a Struct, field: &str) -> Result<&'a Enum, ModelParseError> {
+    member(struct_value, field)?
+        .as_enum()
+        .ok_or_else(|| wrong_type(field, "enum"))
+}
+
+/// Reads an `Option<T>` field where `T` is a Cairo enum, returning the index of the inner variant.
+pub fn get_option_enum_index(
+    struct_value: &Struct,
+    field: &str,
+) -> Result<Option<u8>, ModelParseError> {
+    // Cairo declares `Option<T>` as `{ Some: T, None }`.
+    let option = get_enum(struct_value, field)?;
+    match option.option {
+        Some(0) => option
+            .options
+            .first()
+            .and_then(|variant| variant.ty.as_enum())
+            .map(|inner| inner.option)
+            .ok_or_else(|| wrong_type(field, "Option of enum")),
+        None | Some(1) => Ok(None),
+        Some(_) => Err(wrong_type(field, "Option of enum")),
+    }
+}
+
+/// A model member, decoded without knowing the model.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelValue {
+    Felt(Felt),
+    /// Any unsigned integer up to `u32`.
+    U32(u32),
+    Bool(bool),
+    Address(Felt),
+    Array(Vec<ModelValue>),
+    /// Anything else (structs, enums, wider integers, ...), as received.
+    Other(Ty),
+}
+
+impl From<&Ty> for ModelValue {
+    fn from(ty: &Ty) -> Self {
+        match ty {
+            Ty::Primitive(primitive) => {
+                primitive_value(primitive).unwrap_or_else(|| Self::Other(ty.clone()))
+            }
+            Ty::Array(items) => Self::Array(items.iter().map(Self::from).collect()),
+            _ => Self::Other(ty.clone()),
+        }
+    }
+}
+
+fn primitive_value(primitive: &Primitive) -> Option<ModelValue> {
+    primitive
+        .as_bool()
+        .map(ModelValue::Bool)
+        .or_else(|| primitive.as_contract_address().map(ModelValue::Address))
+        .or_else(|| primitive.as_felt252().map(ModelValue::Felt))
+        .or_else(|| primitive.as_u8().map(|value| ModelValue::U32(value.into())))
+        .or_else(|| {
+            primitive
+                .as_u16()
+                .map(|value| ModelValue::U32(value.into()))
+        })
+        .or_else(|| primitive.as_u32().map(ModelValue::U32))
+}
+
+/// Decodes every member of a model, keyed by member name.
+pub fn model_values(struct_value: &Struct) -> HashMap<String, ModelValue> {
+    struct_value
+        .children
+        .iter()
+        .map(|member| (member.name.clone(), ModelValue::from(&member.ty)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use dojo_types::schema::{EnumOption, Member};
+
+    use super::*;
+
+    /// A model holding an `Option<Direction>` set to the variant at `option`.
+    fn option_model(option: u8, direction: u8) -> Struct {
+        let direction = Enum {
+            name: "Direction".to_string(),
+            option: Some(direction),
+            options: ["Left", "Right", "Up", "Down"]
+                .into_iter()
+                .map(|name| EnumOption {
+                    name: name.to_string(),
+                    ty: Ty::Tuple(vec![]),
+                })
+                .collect(),
+        };
+        let option = Enum {
+            name: "Option<Direction>".to_string(),
+            option: Some(option),
+            options: vec![
+                EnumOption {
+                    name: "Some".to_string(),
+                    ty: Ty::Enum(direction),
+                },
+                EnumOption {
+                    name: "None".to_string(),
+                    ty: Ty::Tuple(vec![]),
+                },
+            ],
+        };
+        Struct {
+            name: "di-Moves".to_string(),
+            children: vec![Member {
+                name: "last_direction".to_string(),
+                ty: Ty::Enum(option),
+                key: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn option_enum_some_is_the_inner_variant() {
+        let model = option_model(0, 2);
+        assert_eq!(get_option_enum_index(&model, "last_direction"), Ok(Some(2)));
+    }
+
+    #[test]
+    fn option_enum_none() {
+        let model = option_model(1, 2);
+        assert_eq!(get_option_enum_index(&model, "last_direction"), Ok(None));
+    }
+
+    #[test]
+    fn fields_of_nested_structs_are_found() {
+        let coordinate = |name: &str, value| Member {
+            name: name.to_string(),
+            ty: Ty::Primitive(Primitive::U32(Some(value))),
+            key: false,
+        };
+        let model = Struct {
+            name: "dojo_starter-Position".to_string(),
+            children: vec![Member {
+                name: "vec".to_string(),
+                ty: Ty::Struct(Struct {
+                    name: "Vec2".to_string(),
+                    children: vec![coordinate("x", 10), coordinate("y", 11)],
+                }),
+                key: false,
+            }],
+        };
+        assert_eq!(get_coordinate(&model, "x"), Ok(10));
+        assert_eq!(get_coordinate(&model, "y"), Ok(11));
+        assert_eq!(
+            get_coordinate(&model, "z"),
+            Err(ModelParseError::MissingField("z".to_string()))
+        );
+    }
+}
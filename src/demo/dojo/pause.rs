@@ -0,0 +1,66 @@
+//! Freezes the world while staying connected, e.g. for a screenshot or a menu.
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::DojoEntityUpdated;
+use std::collections::VecDeque;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<DojoPaused>();
+    app.init_resource::<PauseConfig>();
+    app.init_resource::<PausedUpdates>();
+}
+
+/// While set, Torii updates are buffered instead of applied, and player actions are dropped.
+///
+/// The buffered updates are applied on the first frame after resuming.
+#[derive(Resource, Debug, Default)]
+pub struct DojoPaused(pub bool);
+
+#[derive(Resource, Debug)]
+pub struct PauseConfig {
+    /// Updates kept while paused; the oldest are dropped beyond it.
+    pub max_buffered_updates: usize,
+}
+
+impl Default for PauseConfig {
+    fn default() -> Self {
+        Self {
+            max_buffered_updates: 1024,
+        }
+    }
+}
+
+/// Torii updates received while [`DojoPaused`], oldest first.
+#[derive(Resource, Default)]
+pub struct PausedUpdates {
+    updates: VecDeque<DojoEntityUpdated>,
+    /// Updates dropped since the last resume because the buffer was full.
+    pub dropped: usize,
+}
+
+impl PausedUpdates {
+    pub fn push(&mut self, update: &DojoEntityUpdated, max_len: usize) {
+        self.updates.push_back(DojoEntityUpdated {
+            entity_id: update.entity_id,
+            models: update.models.clone(),
+        });
+        while self.updates.len() > max_len {
+            self.updates.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    /// Takes every buffered update, and resets the `dropped` count.
+    pub fn drain(&mut self) -> Vec<DojoEntityUpdated> {
+        self.dropped = 0;
+        self.updates.drain(..).collect()
+    }
+}
@@ -0,0 +1,374 @@
+//! Saves the players on exit and shows them again on the next launch, until Torii answers.
+//!
+//! Without it, the scene stays empty until the initial sync completes. The restored players are
+//! dimmed as stale, then confirmed or corrected by the live updates. The ones that aren't part
+//! of the world anymore are despawned once the initial sync completes.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use starknet::core::types::Felt;
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use super::{
+    DojoSystemState, LOG_TARGET,
+    appearance::PlayerAppearance,
+    coordinates::GridPosition,
+    intro::{
+        EntityIndex, EntityTracker, PendingSpawn, Player, PlayerDespawnedEvent, Position,
+        PositionUpdatedEvent, update_player_position,
+    },
+    sync::InitialSyncComplete,
+    throttle::DeferredUpdates,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_observer(mark_restored_player);
+    app.add_systems(
+        Startup,
+        restore_state
+            .after(super::setup_dojo_config)
+            .run_if(resource_exists::<StatePersistence>),
+    );
+    app.add_systems(
+        Update,
+        (confirm_restored_players, drop_unconfirmed_players)
+            .chain()
+            .after(update_player_position),
+    );
+    app.add_systems(
+        Last,
+        save_state
+            .run_if(resource_exists::<StatePersistence>)
+            .run_if(on_event::<AppExit>),
+    );
+}
+
+/// Bumped whenever [`PersistedState`] changes, the files of other versions being discarded.
+const STATE_VERSION: u32 = 1;
+
+/// How much darker the restored players are until Torii confirms them.
+const STALE_DIMMING: f32 = 0.3;
+
+/// Enables the persistence of the players, to the file at `path`. Disabled without this
+/// resource, which has to be inserted before startup:
+///
+/// ```ignore
+/// app.insert_resource(StatePersistence::new("world_state.json"));
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct StatePersistence {
+    pub path: PathBuf,
+}
+
+impl StatePersistence {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+/// A player restored from the previous session, not confirmed by Torii yet.
+#[derive(Component, Debug)]
+pub struct Restored {
+    /// The Torii entity holding the position of the player.
+    entity_id: Felt,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PersistedState {
+    version: u32,
+    /// The players of another world are discarded.
+    world_address: Felt,
+    players: Vec<PersistedPlayer>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PersistedPlayer {
+    player: Felt,
+    entity_id: Felt,
+    cell: [i32; 3],
+}
+
+/// Only read to check the version, before the rest of the file is parsed.
+#[derive(Deserialize)]
+struct PersistedVersion {
+    version: u32,
+}
+
+/// The restored players whose cube isn't spawned yet, with their Torii entity.
+#[derive(Resource, Debug, Default)]
+struct RestoredPlayers(HashMap<Felt, Felt>);
+
+/// The saved players of the world at `world_address`, if any.
+fn read_state(
+    persistence: &StatePersistence,
+    world_address: Felt,
+) -> io::Result<Option<PersistedState>> {
+    let contents = match fs::read_to_string(&persistence.path) {
+        Ok(contents) => contents,
+        // Nothing was saved yet.
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    let PersistedVersion { version } = serde_json::from_str(&contents)?;
+    if version != STATE_VERSION {
+        info!(
+            target: LOG_TARGET,
+            version,
+            expected = STATE_VERSION,
+            "Discarding the saved players of another version"
+        );
+        return Ok(None);
+    }
+    let state: PersistedState = serde_json::from_str(&contents)?;
+    if state.world_address != world_address {
+        info!(target: LOG_TARGET, "Discarding the saved players of another world");
+        return Ok(None);
+    }
+    Ok(Some(state))
+}
+
+fn write_state(persistence: &StatePersistence, state: &PersistedState) -> io::Result<()> {
+    let json = serde_json::to_string(state)?;
+    fs::write(&persistence.path, json)
+}
+
+fn restore_state(
+    mut commands: Commands,
+    persistence: Res<StatePersistence>,
+    dojo_state: Res<DojoSystemState>,
+    mut ev_position_updated: EventWriter<PositionUpdatedEvent>,
+) {
+    let state = match read_state(&persistence, dojo_state.config.world_address) {
+        Ok(Some(state)) => state,
+        Ok(None) => return,
+        Err(error) => {
+            warn!(
+                target: LOG_TARGET,
+                path = %persistence.path.display(),
+                %error,
+                "Discarding the saved players"
+            );
+            return;
+        }
+    };
+    info!(target: LOG_TARGET, players = state.players.len(), "Restoring the saved players");
+    let mut restored = RestoredPlayers::default();
+    for PersistedPlayer {
+        player,
+        entity_id,
+        cell: [x, y, z],
+    } in state.players
+    {
+        restored.0.insert(player, entity_id);
+        ev_position_updated.write(PositionUpdatedEvent {
+            position: Position { player, x, y, z },
+            block: None,
+        });
+    }
+    commands.insert_resource(restored);
+}
+
+/// Dims the cube of a restored player as soon as it's spawned.
+fn mark_restored_player(
+    trigger: Trigger<OnAdd, Player>,
+    mut commands: Commands,
+    restored: Option<ResMut<RestoredPlayers>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    players: Query<(&Player, &MeshMaterial3d<StandardMaterial>)>,
+) {
+    let Some(mut restored) = restored else {
+        return;
+    };
+    let Ok((player, material)) = players.get(trigger.target()) else {
+        return;
+    };
+    let Some(entity_id) = restored.0.remove(&player.id) else {
+        return;
+    };
+
+    if let Some(material) = materials.get_mut(&material.0) {
+        material.base_color = material.base_color.darker(STALE_DIMMING);
+    }
+    commands
+        .entity(trigger.target())
+        .insert(Restored { entity_id });
+    if restored.0.is_empty() {
+        commands.remove_resource::<RestoredPlayers>();
+    }
+}
+
+/// Torii sent a position for the entity of the player: whatever the restored position was,
+/// the live one replaced it.
+fn confirm_restored_players(
+    mut commands: Commands,
+    entity_tracker: Res<EntityTracker>,
+    appearance: Res<PlayerAppearance>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    players: Query<(
+        Entity,
+        &Player,
+        &Restored,
+        &MeshMaterial3d<StandardMaterial>,
+    )>,
+) {
+    for (entity, player, restored, material) in &players {
+        if entity_tracker.player_of(restored.entity_id) != Some(player.id) {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = appearance.material(player.id).base_color;
+        }
+        commands.entity(entity).remove::<Restored>();
+    }
+}
+
+/// Despawns the restored players Torii didn't send, once the initial sync and the updates it
+/// deferred were all handled.
+fn drop_unconfirmed_players(
+    mut sync_completed: Local<bool>,
+    deferred: Res<DeferredUpdates>,
+    mut ev_sync_complete: EventReader<InitialSyncComplete>,
+    mut ev_player_despawned: EventWriter<PlayerDespawnedEvent>,
+    players: Query<&Player, With<Restored>>,
+) {
+    if ev_sync_complete.read().count() > 0 {
+        *sync_completed = true;
+    }
+    if !*sync_completed || !deferred.is_empty() {
+        return;
+    }
+
+    *sync_completed = false;
+    for player in &players {
+        debug!(target: LOG_TARGET, player = ?player.id, "Restored player is gone");
+        ev_player_despawned.write(PlayerDespawnedEvent(player.id));
+    }
+}
+
+fn save_state(
+    persistence: Res<StatePersistence>,
+    dojo_state: Res<DojoSystemState>,
+    entity_tracker: Res<EntityTracker>,
+    entity_index: Res<EntityIndex>,
+    cells: Query<(&GridPosition, Option<&Restored>), Without<PendingSpawn>>,
+) {
+    let players: Vec<PersistedPlayer> = entity_tracker
+        .players_by_entity()
+        .filter_map(|(entity_id, player)| {
+            let entity = entity_index.get(player)?;
+            let (GridPosition(cell), _) = cells.get(entity).ok()?;
+            Some(PersistedPlayer {
+                player,
+                entity_id,
+                cell: cell.to_array(),
+            })
+        })
+        .collect();
+    // Restored players never confirmed in this session, e.g. when playing offline.
+    let unconfirmed: Vec<PersistedPlayer> = entity_index
+        .iter()
+        .filter_map(|(player, entity)| {
+            let (GridPosition(cell), restored) = cells.get(entity).ok()?;
+            Some(PersistedPlayer {
+                player,
+                entity_id: restored?.entity_id,
+                cell: cell.to_array(),
+            })
+        })
+        .collect();
+
+    let state = PersistedState {
+        version: STATE_VERSION,
+        world_address: dojo_state.config.world_address,
+        players: players.into_iter().chain(unconfirmed).collect(),
+    };
+    match write_state(&persistence, &state) {
+        Ok(()) => info!(
+            target: LOG_TARGET,
+            path = %persistence.path.display(),
+            players = state.players.len(),
+            "Players saved"
+        ),
+        Err(error) => {
+            error!(
+                target: LOG_TARGET,
+                path = %persistence.path.display(),
+                %error,
+                "Saving the players failed"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file of the temporary directory, removed when dropped.
+    struct TempState(StatePersistence);
+
+    impl TempState {
+        fn new(name: &str) -> Self {
+            let file = format!("bevy_dojo_starter_{name}_{}.json", std::process::id());
+            Self(StatePersistence::new(std::env::temp_dir().join(file)))
+        }
+    }
+
+    impl Drop for TempState {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0.path);
+        }
+    }
+
+    fn state(world_address: Felt) -> PersistedState {
+        PersistedState {
+            version: STATE_VERSION,
+            world_address,
+            players: vec![PersistedPlayer {
+                player: Felt::from(7_u64),
+                entity_id: Felt::from(70_u64),
+                cell: [10, 11, 0],
+            }],
+        }
+    }
+
+    #[test]
+    fn saved_players_are_read_back() {
+        let file = TempState::new("round_trip");
+        let world = Felt::from(1_u64);
+        write_state(&file.0, &state(world)).unwrap();
+
+        assert_eq!(read_state(&file.0, world).unwrap(), Some(state(world)));
+    }
+
+    #[test]
+    fn files_of_another_version_are_discarded() {
+        let file = TempState::new("version");
+        let world = Felt::from(1_u64);
+        write_state(
+            &file.0,
+            &PersistedState {
+                version: 0,
+                ..state(world)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(read_state(&file.0, world).unwrap(), None);
+    }
+
+    #[test]
+    fn files_of_another_world_are_discarded() {
+        let file = TempState::new("world");
+        write_state(&file.0, &state(Felt::from(1_u64))).unwrap();
+
+        assert_eq!(read_state(&file.0, Felt::from(2_u64)).unwrap(), None);
+    }
+
+    #[test]
+    fn a_missing_file_is_no_error() {
+        let file = TempState::new("missing");
+
+        assert_eq!(read_state(&file.0, Felt::from(1_u64)).unwrap(), None);
+    }
+}
@@ -0,0 +1,213 @@
+//! Client-side prediction of the local player's moves.
+//!
+//! Submitted moves are applied to the local cube right away, then reconciled with the positions
+//! pushed by Torii once the transactions land. Remote players are only interpolated.
+
+use bevy::prelude::*;
+use std::{collections::VecDeque, time::Duration};
+
+use super::{
+    LOG_TARGET,
+    actions::MoveSubmitted,
+    coordinates::CoordinateScale,
+    intro::{
+        LocalPlayer, Player, PlayerInterpolation, PositionUpdatedEvent, interpolate_players,
+        update_player_position,
+    },
+};
+use crate::AppSystems;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PredictionConfig>();
+    app.init_resource::<MovementStep>();
+    app.add_systems(
+        Update,
+        (
+            start_prediction,
+            predict_local_moves.in_set(AppSystems::Update),
+            reconcile_prediction
+                .after(update_player_position)
+                .before(interpolate_players),
+        )
+            .chain(),
+    );
+}
+
+/// Enables the prediction of the local player's moves.
+#[derive(Resource, Debug)]
+pub struct PredictionConfig {
+    pub enabled: bool,
+    /// Distance in cells under which a predicted position is considered confirmed by Torii.
+    /// Beyond it, the cube snaps back to the authoritative position.
+    pub max_correction: f32,
+    /// Predictions unconfirmed for this long are dropped, e.g. when a move was a no-op onchain.
+    pub timeout: Duration,
+}
+
+impl Default for PredictionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_correction: 0.5,
+            timeout: Duration::from_secs(3),
+        }
+    }
+}
+
+/// Cells covered by one move of the `actions` contract, for the contracts moving by more
+/// than one cell. Only the predicted positions use it, the ones from Torii are applied as is.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct MovementStep(pub u32);
+
+impl Default for MovementStep {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// The predicted state of the [`LocalPlayer`].
+#[derive(Component, Debug, Default)]
+pub struct Prediction {
+    /// Last position pushed by Torii.
+    pub authoritative: Vec3,
+    /// Predicted positions not confirmed yet, oldest first, with the elapsed app time of the move.
+    pending: VecDeque<(Vec3, Duration)>,
+}
+
+impl Prediction {
+    /// The position the player is expected to end up at.
+    pub fn predicted(&self) -> Vec3 {
+        self.pending
+            .back()
+            .map_or(self.authoritative, |(position, _)| *position)
+    }
+
+    fn reset(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Drops the pending predictions and moves the player to `authoritative`.
+    pub fn snap_to(&mut self, authoritative: Vec3) {
+        self.reset();
+        self.authoritative = authoritative;
+    }
+}
+
+/// How a move changes the position, matching `next_position` in the `actions` contract, for a
+/// [`MovementStep`] of one.
+pub fn direction_delta(direction: u8) -> Option<Vec3> {
+    Some(match direction {
+        0 => Vec3::NEG_X,
+        1 => Vec3::X,
+        2 => Vec3::NEG_Y,
+        3 => Vec3::Y,
+        4 => Vec3::Z,
+        5 => Vec3::NEG_Z,
+        _ => return None,
+    })
+}
+
+fn start_prediction(
+    mut commands: Commands,
+    query: Query<(Entity, &PlayerInterpolation), Added<LocalPlayer>>,
+) {
+    for (entity, interpolation) in &query {
+        commands.entity(entity).insert(Prediction {
+            authoritative: interpolation.target,
+            ..default()
+        });
+    }
+}
+
+fn predict_local_moves(
+    time: Res<Time>,
+    config: Res<PredictionConfig>,
+    scale: Res<CoordinateScale>,
+    step: Res<MovementStep>,
+    mut ev_move_submitted: EventReader<MoveSubmitted>,
+    mut local_player: Query<(&mut PlayerInterpolation, &mut Prediction), With<LocalPlayer>>,
+) {
+    let Ok((mut interpolation, mut prediction)) = local_player.single_mut() else {
+        ev_move_submitted.clear();
+        return;
+    };
+    if !config.enabled {
+        ev_move_submitted.clear();
+        return;
+    }
+
+    for MoveSubmitted { direction } in ev_move_submitted.read() {
+        let Some(delta) = direction_delta(*direction) else {
+            continue;
+        };
+        let predicted = prediction.predicted() + delta * step.0 as f32 * scale.units_per_cell;
+        prediction.pending.push_back((predicted, time.elapsed()));
+        interpolation.target = predicted;
+    }
+}
+
+/// Runs after `update_player_position`, which set the interpolation target to the position
+/// from Torii, and puts back the predicted target when the update confirms the prediction.
+fn reconcile_prediction(
+    time: Res<Time>,
+    config: Res<PredictionConfig>,
+    scale: Res<CoordinateScale>,
+    mut ev_position_updated: EventReader<PositionUpdatedEvent>,
+    mut local_player: Query<
+        (
+            &Player,
+            &mut Transform,
+            &mut PlayerInterpolation,
+            &mut Prediction,
+        ),
+        With<LocalPlayer>,
+    >,
+) {
+    let Ok((player, mut transform, mut interpolation, mut prediction)) = local_player.single_mut()
+    else {
+        ev_position_updated.clear();
+        return;
+    };
+
+    let max_correction = config.max_correction * scale.units_per_cell;
+    for ev in ev_position_updated.read() {
+        if ev.position.player != player.id {
+            continue;
+        }
+
+        let authoritative = scale.to_world(ev.position.as_vec3());
+        let previous = std::mem::replace(&mut prediction.authoritative, authoritative);
+        if prediction.pending.is_empty() {
+            continue;
+        }
+
+        let confirmed = prediction
+            .pending
+            .iter()
+            .position(|(predicted, _)| predicted.distance(authoritative) <= max_correction);
+        match confirmed {
+            Some(index) => {
+                prediction.pending.drain(..=index);
+            }
+            // An update that doesn't move the player, e.g. for another model field.
+            None if previous.distance(authoritative) <= max_correction => {}
+            None => {
+                debug!(target: LOG_TARGET, ?authoritative, "Prediction rejected, snapping back");
+                prediction.reset();
+                transform.translation = authoritative;
+            }
+        }
+        interpolation.target = prediction.predicted();
+    }
+
+    let expired = prediction
+        .pending
+        .front()
+        .is_some_and(|(_, at)| time.elapsed().saturating_sub(*at) > config.timeout);
+    if expired {
+        debug!(target: LOG_TARGET, "Prediction timed out, snapping back");
+        prediction.reset();
+        transform.translation = prediction.authoritative;
+        interpolation.target = prediction.authoritative;
+    }
+}
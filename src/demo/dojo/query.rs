@@ -0,0 +1,184 @@
+//! A fluent builder for Torii queries.
+//!
+//! Entities can be filtered by keys with [`DojoQueryBuilder::keys`], or by member values with
+//! [`member_clause`]. Clauses combine with [`and`] and [`or`], so "x between 0 and 100" reads:
+//!
+//! ```ignore
+//! let clause = and([
+//!     member_clause("di-Position", "x", ComparisonOperator::Gte, Primitive::U32(Some(0))),
+//!     member_clause("di-Position", "x", ComparisonOperator::Lte, Primitive::U32(Some(100))),
+//! ]);
+//! let query = DojoQueryBuilder::new().clause(clause).build();
+//! ```
+
+use dojo_types::primitive::Primitive;
+use starknet::core::types::Felt;
+use torii_grpc_client::types::{
+    Clause, ComparisonOperator, CompositeClause, KeysClause, LogicalOperator, MemberClause,
+    MemberValue, OrderBy, OrderDirection, Pagination, PaginationDirection, PatternMatching,
+    Query as ToriiQuery,
+};
+
+/// Builds a [`ToriiQuery`] without spelling out every field.
+///
+/// Defaults to the first 50 entities, paginating forward, without any clause.
+///
+/// ```ignore
+/// let query = DojoQueryBuilder::new()
+///     .limit(100)
+///     .models(["di-Position"])
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct DojoQueryBuilder {
+    query: ToriiQuery,
+}
+
+impl Default for DojoQueryBuilder {
+    fn default() -> Self {
+        Self {
+            query: ToriiQuery {
+                clause: None,
+                pagination: Pagination {
+                    limit: 50,
+                    cursor: None,
+                    direction: PaginationDirection::Forward,
+                    order_by: vec![],
+                },
+                no_hashed_keys: false,
+                models: vec![],
+                historical: false,
+            },
+        }
+    }
+}
+
+impl DojoQueryBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of entities per page.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.query.pagination.limit = limit;
+        self
+    }
+
+    /// Resumes from the cursor returned with a previous page.
+    pub fn cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.query.pagination.cursor = Some(cursor.into());
+        self
+    }
+
+    pub fn forward(mut self) -> Self {
+        self.query.pagination.direction = PaginationDirection::Forward;
+        self
+    }
+
+    pub fn backward(mut self) -> Self {
+        self.query.pagination.direction = PaginationDirection::Backward;
+        self
+    }
+
+    /// Sorts the entities by a member of a model, e.g. `di-Position.player`. Without any order,
+    /// Torii returns the entities in an unspecified order. When called several times, the
+    /// first order takes precedence.
+    pub fn order_by(
+        mut self,
+        model: impl Into<String>,
+        member: impl Into<String>,
+        direction: OrderDirection,
+    ) -> Self {
+        self.query.pagination.order_by.push(OrderBy {
+            model: model.into(),
+            member: member.into(),
+            direction,
+        });
+        self
+    }
+
+    /// Only returns these models, e.g. `["di-Position"]`. Empty means every model.
+    pub fn models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.query.models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Filters the entities.
+    pub fn clause(mut self, clause: Clause) -> Self {
+        self.query.clause = Some(clause);
+        self
+    }
+
+    /// Only returns entities of `models` whose keys match. `None` matches any key, and entities
+    /// with more keys than given still match.
+    pub fn keys<I, S>(self, keys: Vec<Option<Felt>>, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.clause(keys_clause(keys, models))
+    }
+
+    /// Whether to return every past state instead of the latest one.
+    pub fn historical(mut self, historical: bool) -> Self {
+        self.query.historical = historical;
+        self
+    }
+
+    pub fn no_hashed_keys(mut self, no_hashed_keys: bool) -> Self {
+        self.query.no_hashed_keys = no_hashed_keys;
+        self
+    }
+
+    pub fn build(self) -> ToriiQuery {
+        self.query
+    }
+}
+
+/// Matches entities of `models` by their keys, `None` being a wildcard.
+pub fn keys_clause<I, S>(keys: Vec<Option<Felt>>, models: I) -> Clause
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    Clause::Keys(KeysClause {
+        keys,
+        pattern_matching: PatternMatching::VariableLen,
+        models: models.into_iter().map(Into::into).collect(),
+    })
+}
+
+/// Compares a member of a model, e.g. `di-Position.x >= 0`.
+pub fn member_clause(
+    model: impl Into<String>,
+    member: impl Into<String>,
+    operator: ComparisonOperator,
+    value: Primitive,
+) -> Clause {
+    Clause::Member(MemberClause {
+        model: model.into(),
+        member: member.into(),
+        operator,
+        value: MemberValue::Primitive(value),
+    })
+}
+
+/// Matches entities matching every clause.
+pub fn and(clauses: impl IntoIterator<Item = Clause>) -> Clause {
+    Clause::Composite(CompositeClause {
+        operator: LogicalOperator::And,
+        clauses: clauses.into_iter().collect(),
+    })
+}
+
+/// Matches entities matching any clause.
+pub fn or(clauses: impl IntoIterator<Item = Clause>) -> Clause {
+    Clause::Composite(CompositeClause {
+        operator: LogicalOperator::Or,
+        clauses: clauses.into_iter().collect(),
+    })
+}
@@ -0,0 +1,280 @@
+//! Reconnects the account once it dropped, e.g. when Katana restarts during development.
+//!
+//! The connected account is probed every [`ReconnectPolicy::probe_interval`]. When a probe or a
+//! connection attempt fails, the account is connected again after a growing delay, and the
+//! transactions submitted meanwhile are held by the [`TransactionTracker`] until it's back.
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::{DojoResource, TokioRuntime};
+use starknet::{accounts::ConnectedAccount, providers::Provider};
+use std::{
+    sync::{
+        Mutex,
+        mpsc::{Receiver, Sender, channel},
+    },
+    time::Duration,
+};
+
+use super::{
+    AccountConnectedEvent, AccountConnectionFailedEvent, DojoDisconnected, DojoSystemState,
+    LOG_TARGET, PendingAccountConnection,
+    error::{DojoError, DojoErrorEvent},
+    task,
+    tx::TransactionTracker,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ReconnectPolicy>();
+    app.init_resource::<AccountReconnect>();
+    app.add_systems(
+        Update,
+        (
+            reset_account_reconnect.run_if(on_event::<DojoDisconnected>),
+            (probe_account, receive_account_probes)
+                .chain()
+                .run_if(|dojo_state: Res<DojoSystemState>| dojo_state.account_connected),
+            schedule_account_reconnect.run_if(on_event::<AccountConnectionFailedEvent>),
+            reconnect_account
+                .run_if(resource_exists::<TokioRuntime>)
+                .run_if(not(resource_exists::<PendingAccountConnection>)),
+            release_held_transactions
+                .run_if(on_event::<AccountConnectedEvent>)
+                .run_if(resource_exists::<TokioRuntime>),
+        )
+            .chain()
+            .after(super::apply_account_connection),
+    );
+}
+
+/// When the account is probed, and how long to wait between two reconnection attempts.
+///
+/// The delay starts at `initial_delay` and doubles after every failed attempt, up to
+/// `max_delay`.
+#[derive(Resource, Debug, Clone)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// Reconnection gives up after this many attempts in a row, `None` retrying forever.
+    pub max_attempts: Option<u32>,
+    /// Delay between two probes of the connected account.
+    pub probe_interval: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+            probe_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay before the `attempt`-th reconnection, counted from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// The outcome of one probe: `None`, or the error.
+type ProbeResult = Option<String>;
+
+/// The reconnection in progress, if any.
+#[derive(Resource)]
+pub struct AccountReconnect {
+    /// Failed attempts in a row, 0 when connected.
+    attempt: u32,
+    /// Elapsed app time of the next attempt.
+    next_attempt_at: Option<Duration>,
+    since_last_probe: Duration,
+    probe_in_flight: bool,
+    sender: Sender<ProbeResult>,
+    receiver: Mutex<Receiver<ProbeResult>>,
+}
+
+impl Default for AccountReconnect {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            attempt: 0,
+            next_attempt_at: None,
+            since_last_probe: Duration::ZERO,
+            probe_in_flight: false,
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+impl AccountReconnect {
+    pub fn is_reconnecting(&self) -> bool {
+        self.attempt > 0
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Schedules the next attempt, or returns `false` once `policy` gives up.
+    fn schedule(&mut self, now: Duration, policy: &ReconnectPolicy) -> bool {
+        self.attempt += 1;
+        if policy
+            .max_attempts
+            .is_some_and(|max_attempts| self.attempt > max_attempts)
+        {
+            self.attempt = 0;
+            self.next_attempt_at = None;
+            return false;
+        }
+        let delay = policy.delay(self.attempt);
+        info!(target: LOG_TARGET, attempt = self.attempt, ?delay, "Reconnecting the account");
+        self.next_attempt_at = Some(now + delay);
+        true
+    }
+}
+
+/// The connections were closed on purpose, nothing to reconnect.
+fn reset_account_reconnect(mut reconnect: ResMut<AccountReconnect>) {
+    *reconnect = AccountReconnect::default();
+}
+
+/// Asks the RPC for the latest block through the account every [`ReconnectPolicy::probe_interval`].
+fn probe_account(
+    time: Res<Time>,
+    tokio: Option<Res<TokioRuntime>>,
+    dojo: Res<DojoResource>,
+    policy: Res<ReconnectPolicy>,
+    mut reconnect: ResMut<AccountReconnect>,
+) {
+    reconnect.since_last_probe += time.delta();
+    if reconnect.probe_in_flight || reconnect.since_last_probe < policy.probe_interval {
+        return;
+    }
+    let (Some(tokio), Some(account)) = (tokio, dojo.sn.account.clone()) else {
+        return;
+    };
+    reconnect.since_last_probe = Duration::ZERO;
+    reconnect.probe_in_flight = true;
+
+    let sender = reconnect.sender.clone();
+    task::spawn(&tokio, async move {
+        let result = account.provider().block_number().await;
+        let _ = sender.send(result.err().map(|error| error.to_string()));
+    });
+}
+
+fn receive_account_probes(
+    time: Res<Time>,
+    policy: Res<ReconnectPolicy>,
+    mut dojo: ResMut<DojoResource>,
+    mut dojo_state: ResMut<DojoSystemState>,
+    mut reconnect: ResMut<AccountReconnect>,
+    mut tx_tracker: ResMut<TransactionTracker>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    let results: Vec<ProbeResult> = reconnect
+        .receiver
+        .lock()
+        .map(|receiver| receiver.try_iter().collect())
+        .unwrap_or_default();
+
+    for result in results {
+        reconnect.probe_in_flight = false;
+        let Some(error) = result else {
+            continue;
+        };
+        // A probe may fail while the account was already dropped by another one.
+        if !dojo_state.account_connected {
+            continue;
+        }
+
+        error!(target: LOG_TARGET, "Katana account lost: {}", error);
+        ev_error.write(DojoError::ConnectionFailed(format!("Katana account lost: {error}")).into());
+        dojo_state.account_connected = false;
+        // Cleared so the next connection is only seen once the plugin made it.
+        dojo.sn.account = None;
+        tx_tracker.hold();
+        if !reconnect.schedule(time.elapsed(), &policy) {
+            tx_tracker.fail_queued("account disconnected");
+        }
+    }
+}
+
+/// The initial connection, or a reconnection, didn't complete.
+fn schedule_account_reconnect(
+    time: Res<Time>,
+    policy: Res<ReconnectPolicy>,
+    mut reconnect: ResMut<AccountReconnect>,
+    mut tx_tracker: ResMut<TransactionTracker>,
+    mut ev_failed: EventReader<AccountConnectionFailedEvent>,
+) {
+    ev_failed.clear();
+    if reconnect.schedule(time.elapsed(), &policy) {
+        tx_tracker.hold();
+    } else {
+        warn!(target: LOG_TARGET, "Giving up reconnecting the account");
+        tx_tracker.fail_queued("account reconnection gave up");
+    }
+}
+
+fn reconnect_account(
+    mut commands: Commands,
+    time: Res<Time>,
+    tokio: Res<TokioRuntime>,
+    mut dojo: ResMut<DojoResource>,
+    dojo_state: Res<DojoSystemState>,
+    mut reconnect: ResMut<AccountReconnect>,
+    mut tx_tracker: ResMut<TransactionTracker>,
+) {
+    let now = time.elapsed();
+    if reconnect.next_attempt_at.is_none_or(|at| now < at) {
+        return;
+    }
+    reconnect.next_attempt_at = None;
+
+    if !super::connect_account(&mut commands, now, &tokio, &mut dojo, &dojo_state.config) {
+        // The account was connected manually, there is no configuration to connect it again.
+        warn!(target: LOG_TARGET, "No account configured, not reconnecting");
+        *reconnect = AccountReconnect::default();
+        tx_tracker.fail_queued("account disconnected");
+    }
+}
+
+/// Sends the transactions submitted while the account was away.
+fn release_held_transactions(
+    time: Res<Time>,
+    tokio: Res<TokioRuntime>,
+    dojo: Res<DojoResource>,
+    mut reconnect: ResMut<AccountReconnect>,
+    mut tx_tracker: ResMut<TransactionTracker>,
+) {
+    if reconnect.is_reconnecting() {
+        info!(target: LOG_TARGET, attempt = reconnect.attempt, "Account reconnected");
+        reconnect.attempt = 0;
+        reconnect.next_attempt_at = None;
+    }
+    if tx_tracker.is_holding() {
+        tx_tracker.release(&dojo, &tokio, time.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_the_max() {
+        let policy = ReconnectPolicy::default();
+
+        let delays: Vec<u64> = (1..=7)
+            .map(|attempt| policy.delay(attempt).as_secs())
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(policy.delay(u32::MAX), policy.max_delay);
+    }
+}
@@ -0,0 +1,324 @@
+//! Map Torii models to the handlers turning them into Bevy events.
+//!
+//! Registering a model is enough to have it fetched at startup and dispatched on every update:
+//!
+//! ```ignore
+//! app.world_mut()
+//!     .resource_mut::<ModelRegistry>()
+//!     .register("di-Health", |_entity_id, model, commands| {
+//!         commands.send_event(HealthUpdatedEvent(Health::try_from(model)?));
+//!         Ok(())
+//!     });
+//! ```
+//!
+//! Models only stored on the entities go through [`RegisterModelExt::register_model`]
+//! instead, which needs neither an event nor a handler:
+//!
+//! ```ignore
+//! app.register_model::<Health>("di-Health");
+//! ```
+//!
+//! A model deriving [`DojoModel`] registers itself as its attributes say:
+//!
+//! ```ignore
+//! #[derive(Component, DojoModel)]
+//! #[dojo(name = "di-Health", event = HealthUpdatedEvent, component)]
+//! pub struct Health { ... }
+//!
+//! Health::register(app);
+//! ```
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use dojo_types::schema::Struct;
+use starknet::core::types::Felt;
+use std::{collections::HashMap, time::Duration};
+
+use super::{
+    DojoSystemState, LOG_TARGET,
+    models::{ModelParseError, ModelValue},
+};
+use crate::constants::dojo::namespaced_model_name;
+
+pub use bevy_dojo_derive::DojoModel;
+
+/// What the code generated by `#[derive(DojoModel)]` refers to.
+#[doc(hidden)]
+pub mod __private {
+    pub use bevy::app::App;
+    pub use dojo_types;
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ModelRegistry>();
+    app.init_resource::<ModelComponents>();
+    app.add_event::<RawModelUpdated>();
+    app.add_systems(
+        PreUpdate,
+        apply_model_namespace.run_if(resource_changed::<DojoSystemState>),
+    );
+}
+
+/// Emitted for every model of every Torii update, registered or not, before its handler runs.
+///
+/// Lets another plugin follow arbitrary models without registering a handler.
+#[derive(Event, Debug, Clone)]
+pub struct RawModelUpdated {
+    pub entity_id: Felt,
+    pub model_name: String,
+    pub fields: HashMap<String, ModelValue>,
+}
+
+/// Handles one model of a Torii update, given the Torii entity id it belongs to.
+///
+/// Events and world changes go through `commands`, so they are applied before the systems
+/// ordered after `on_dojo_events`.
+pub type ModelHandler =
+    Box<dyn Fn(Felt, &Struct, &mut Commands) -> Result<(), ModelParseError> + Send + Sync>;
+
+/// A Rust type mapped from a Torii model, usually through `#[derive(DojoModel)]`.
+pub trait DojoModel: for<'a> TryFrom<&'a Struct, Error = ModelParseError> {
+    /// Full model name, including the namespace (e.g. `di-Position`).
+    const NAME: &'static str;
+
+    /// Registers the model as [`NAME`](Self::NAME), as its `#[dojo(...)]` attributes say: its
+    /// updates emitted as an `event`, handed to a `handler`, stored as a `component` on the
+    /// player, or only parsed.
+    fn register(app: &mut App);
+}
+
+/// The models the game knows about, in registration order.
+///
+/// Models are registered with the name they are declared with, e.g. `di-Position`, and matched
+/// against the Torii models in the namespace of the
+/// [`DojoConfig`](crate::constants::dojo::DojoConfig::namespace).
+#[derive(Resource, Default)]
+pub struct ModelRegistry {
+    handlers: Vec<RegisteredModel>,
+    /// The declared names are kept as is when `None`.
+    namespace: Option<String>,
+}
+
+struct RegisteredModel {
+    /// Name the model was registered with.
+    declared: String,
+    /// Name Torii sends the model with.
+    name: String,
+    handler: ModelHandler,
+}
+
+impl ModelRegistry {
+    /// Registers the handler of a model, replacing any previous one for the same name.
+    ///
+    /// `name` is the full model name, including the namespace (e.g. `di-Position`).
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        handler: impl Fn(Felt, &Struct, &mut Commands) -> Result<(), ModelParseError>
+        + Send
+        + Sync
+        + 'static,
+    ) -> &mut Self {
+        let declared = name.into();
+        self.handlers
+            .retain(|registered| registered.declared != declared);
+        self.handlers.push(RegisteredModel {
+            name: self.resolve(&declared),
+            declared,
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// Registers a model stored as the component `T`, see [`RegisterModelExt::register_model`].
+    ///
+    /// A handler already registered for `name` keeps running, before the component is queued.
+    pub fn register_component<T>(&mut self, name: impl Into<String>) -> &mut Self
+    where
+        T: for<'a> TryFrom<&'a Struct, Error = ModelParseError> + Component,
+    {
+        let name = name.into();
+        let previous = self
+            .handlers
+            .iter()
+            .position(|registered| registered.declared == name)
+            .map(|index| self.handlers.remove(index).handler);
+        self.register(name, move |entity_id, model, commands| {
+            if let Some(previous) = &previous {
+                previous(entity_id, model, commands)?;
+            }
+            let component = T::try_from(model)?;
+            commands.queue(move |world: &mut World| {
+                let received_at = world.resource::<Time>().elapsed();
+                world.resource_mut::<ModelComponents>().push(
+                    entity_id,
+                    received_at,
+                    Box::new(move |entity: &mut EntityCommands| {
+                        entity.insert(component);
+                    }),
+                );
+            });
+            Ok(())
+        })
+    }
+
+    /// Registers a [`DojoModel`] whose updates are emitted as the event built by `to_event`.
+    pub fn register_model_event<M, E>(
+        &mut self,
+        to_event: impl Fn(M) -> E + Send + Sync + 'static,
+    ) -> &mut Self
+    where
+        M: DojoModel,
+        E: Event,
+    {
+        self.register(M::NAME, move |_entity_id, model, commands| {
+            commands.send_event(to_event(M::try_from(model)?));
+            Ok(())
+        })
+    }
+
+    /// The handler of the model Torii sends as `name`, in the current namespace.
+    pub fn get(&self, name: &str) -> Option<&ModelHandler> {
+        self.handlers
+            .iter()
+            .find(|registered| registered.name == name)
+            .map(|registered| &registered.handler)
+    }
+
+    /// Names of the registered models in the current namespace, e.g. to only fetch those from
+    /// Torii.
+    pub fn model_names(&self) -> impl Iterator<Item = &str> {
+        self.handlers
+            .iter()
+            .map(|registered| registered.name.as_str())
+    }
+
+    /// Matches the models declared in the default namespace against the ones in `namespace`.
+    pub fn set_namespace(&mut self, namespace: &str) {
+        self.namespace = Some(namespace.to_string());
+        for registered in &mut self.handlers {
+            registered.name = namespaced_model_name(namespace, &registered.declared);
+        }
+    }
+
+    fn resolve(&self, declared: &str) -> String {
+        self.namespace.as_deref().map_or_else(
+            || declared.to_string(),
+            |namespace| namespaced_model_name(namespace, declared),
+        )
+    }
+}
+
+/// Registers models on the [`App`] directly.
+pub trait RegisterModelExt {
+    /// Stores the model `name` as the component `T`, inserted or replaced on the entity of the
+    /// player the Torii entity belongs to on every update.
+    ///
+    /// The model is fetched and subscribed to like the others. Until the player is known from
+    /// its `Position`, the component waits in [`ModelComponents`].
+    fn register_model<T>(&mut self, name: impl Into<String>) -> &mut Self
+    where
+        T: for<'a> TryFrom<&'a Struct, Error = ModelParseError> + Component;
+}
+
+impl RegisterModelExt for App {
+    fn register_model<T>(&mut self, name: impl Into<String>) -> &mut Self
+    where
+        T: for<'a> TryFrom<&'a Struct, Error = ModelParseError> + Component,
+    {
+        self.init_resource::<ModelRegistry>()
+            .world_mut()
+            .resource_mut::<ModelRegistry>()
+            .register_component::<T>(name);
+        self
+    }
+}
+
+/// Inserts a parsed model, as a component, on the entity it belongs to.
+pub type ComponentInsert = Box<dyn FnOnce(&mut EntityCommands) + Send + Sync>;
+
+/// A component waiting for the player of its Torii entity.
+pub(super) struct PendingComponent {
+    pub entity_id: Felt,
+    /// Elapsed app time the model was received at.
+    pub received_at: Duration,
+    pub insert: ComponentInsert,
+}
+
+/// The components parsed from the models registered with [`RegisterModelExt::register_model`],
+/// not inserted yet, with the Torii entity id they belong to. Oldest first.
+///
+/// Components of a Torii entity whose `Position` never arrives are dropped after `timeout`.
+#[derive(Resource)]
+pub struct ModelComponents {
+    pending: Vec<PendingComponent>,
+    pub timeout: Duration,
+}
+
+impl Default for ModelComponents {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ModelComponents {
+    pub fn push(&mut self, entity_id: Felt, received_at: Duration, insert: ComponentInsert) {
+        self.pending.push(PendingComponent {
+            entity_id,
+            received_at,
+            insert,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Drops the components of a Torii entity, e.g. once it was deleted.
+    pub fn forget(&mut self, entity_id: Felt) {
+        self.pending
+            .retain(|pending| pending.entity_id != entity_id);
+    }
+
+    /// Drops the components waiting for longer than `timeout`, returning how many.
+    pub fn prune(&mut self, now: Duration) -> usize {
+        let before = self.pending.len();
+        let timeout = self.timeout;
+        self.pending
+            .retain(|pending| now.saturating_sub(pending.received_at) < timeout);
+        before - self.pending.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    pub(super) fn take(&mut self) -> Vec<PendingComponent> {
+        std::mem::take(&mut self.pending)
+    }
+
+    pub(super) fn set(&mut self, pending: Vec<PendingComponent>) {
+        self.pending = pending;
+    }
+}
+
+/// Follows the namespace of the current config, e.g. after switching worlds.
+fn apply_model_namespace(dojo_state: Res<DojoSystemState>, mut registry: ResMut<ModelRegistry>) {
+    let namespace = &dojo_state.config.namespace;
+    if registry.namespace.as_ref() == Some(namespace) {
+        return;
+    }
+    registry.set_namespace(namespace);
+    info!(
+        target: LOG_TARGET,
+        %namespace,
+        models = ?registry.model_names().collect::<Vec<_>>(),
+        "Matching the Torii models of the namespace"
+    );
+}
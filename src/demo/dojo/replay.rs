@@ -0,0 +1,185 @@
+//! Plays back recorded Torii updates, to run the game without any backend.
+//!
+//! Set `TORII_REPLAY` to a file written by the recorder: no connection is made, and the
+//! updates are emitted as [`DojoEntityUpdated`] at the pace they were recorded.
+//! With the `torii_record` feature, `TORII_RECORD` names the file the live updates are
+//! written to when the app exits.
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::DojoEntityUpdated;
+use dojo_types::schema::Struct;
+use serde::{Deserialize, Serialize};
+use starknet::core::types::Felt;
+use std::{env, fs, io, path::Path, time::Duration};
+
+use super::{
+    LOG_TARGET,
+    error::{DojoError, DojoErrorEvent},
+};
+use crate::constants::dojo::ConfigError;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PreUpdate,
+        play_replay.run_if(resource_exists::<ToriiReplay>),
+    );
+
+    #[cfg(feature = "torii_record")]
+    app.add_systems(Startup, start_recording)
+        .add_systems(
+            Update,
+            record_updates.run_if(resource_exists::<ToriiRecorder>),
+        )
+        .add_systems(
+            Last,
+            save_recording
+                .run_if(resource_exists::<ToriiRecorder>)
+                .run_if(on_event::<AppExit>),
+        );
+}
+
+/// One recorded [`DojoEntityUpdated`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayRecord {
+    /// Seconds since the recording started.
+    pub at: f64,
+    pub entity_id: Felt,
+    pub models: Vec<Struct>,
+}
+
+/// Recorded updates being played back, oldest first.
+#[derive(Resource, Debug)]
+pub struct ToriiReplay {
+    records: Vec<ReplayRecord>,
+    next: usize,
+    started_at: Option<Duration>,
+}
+
+impl ToriiReplay {
+    pub fn new(mut records: Vec<ReplayRecord>) -> Self {
+        records.sort_by(|a, b| a.at.total_cmp(&b.at));
+        Self {
+            records,
+            next: 0,
+            started_at: None,
+        }
+    }
+
+    /// Reads a JSON array of [`ReplayRecord`].
+    pub fn from_path(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(Self::new(serde_json::from_str(&contents)?))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.records.len()
+    }
+}
+
+/// Loads the replay named by `TORII_REPLAY`, which replaces the Dojo connection.
+pub(super) fn load_replay(mut commands: Commands, mut ev_error: EventWriter<DojoErrorEvent>) {
+    let Ok(path) = env::var("TORII_REPLAY") else {
+        return;
+    };
+
+    match ToriiReplay::from_path(Path::new(&path)) {
+        Ok(replay) => {
+            info!(
+                target: LOG_TARGET,
+                %path,
+                records = replay.records.len(),
+                "Replaying recorded Torii updates"
+            );
+            commands.insert_resource(replay);
+        }
+        Err(source) => {
+            ev_error.write(DojoError::from(ConfigError::Io { path, source }).into());
+        }
+    }
+}
+
+fn play_replay(
+    time: Res<Time>,
+    mut replay: ResMut<ToriiReplay>,
+    mut ev_entity_updated: EventWriter<DojoEntityUpdated>,
+) {
+    if replay.is_finished() {
+        return;
+    }
+
+    let started_at = *replay.started_at.get_or_insert(time.elapsed());
+    let elapsed = time.elapsed().saturating_sub(started_at).as_secs_f64();
+    while let Some(record) = replay.records.get(replay.next) {
+        if record.at > elapsed {
+            break;
+        }
+        ev_entity_updated.write(DojoEntityUpdated {
+            entity_id: record.entity_id,
+            models: record.models.clone(),
+        });
+        replay.next += 1;
+    }
+
+    if replay.is_finished() {
+        info!(target: LOG_TARGET, "Replay finished");
+    }
+}
+
+/// Live updates collected until the app exits.
+#[cfg(feature = "torii_record")]
+#[derive(Resource, Debug)]
+pub struct ToriiRecorder {
+    path: String,
+    started_at: Duration,
+    records: Vec<ReplayRecord>,
+}
+
+#[cfg(feature = "torii_record")]
+fn start_recording(mut commands: Commands, time: Res<Time>) {
+    let Ok(path) = env::var("TORII_RECORD") else {
+        return;
+    };
+    info!(target: LOG_TARGET, %path, "Recording Torii updates");
+    commands.insert_resource(ToriiRecorder {
+        path,
+        started_at: time.elapsed(),
+        records: Vec::new(),
+    });
+}
+
+#[cfg(feature = "torii_record")]
+fn record_updates(
+    time: Res<Time>,
+    mut recorder: ResMut<ToriiRecorder>,
+    mut ev_entity_updated: EventReader<DojoEntityUpdated>,
+) {
+    let at = time
+        .elapsed()
+        .saturating_sub(recorder.started_at)
+        .as_secs_f64();
+    for ev in ev_entity_updated.read() {
+        recorder.records.push(ReplayRecord {
+            at,
+            entity_id: ev.entity_id,
+            models: ev.models.clone(),
+        });
+    }
+}
+
+#[cfg(feature = "torii_record")]
+fn save_recording(recorder: Res<ToriiRecorder>) {
+    let result = serde_json::to_string(&recorder.records)
+        .map_err(io::Error::from)
+        .and_then(|json| fs::write(&recorder.path, json));
+    match result {
+        Ok(()) => info!(
+            target: LOG_TARGET,
+            path = %recorder.path,
+            records = recorder.records.len(),
+            "Torii updates recorded"
+        ),
+        Err(error) => {
+            error!(target: LOG_TARGET, path = %recorder.path, %error, "Recording failed");
+        }
+    }
+}
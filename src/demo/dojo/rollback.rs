@@ -0,0 +1,343 @@
+//! Rollback reconciliation of the local player's moves.
+//!
+//! Every submitted move is applied to the local cube right away and kept, timestamped, in an
+//! [`InputBuffer`]. When Torii pushes an authoritative position, the cube is rewound to it and
+//! the moves it doesn't include yet are applied again on top. Unlike the prediction, which
+//! snaps back when Torii disagrees, a rejected move only discards itself, not the moves made
+//! after it. Remote players are only interpolated.
+
+use bevy::prelude::*;
+use std::{collections::VecDeque, time::Duration};
+
+use super::{
+    LOG_TARGET,
+    actions::MoveSubmitted,
+    coordinates::{CoordinateScale, GridPosition},
+    intro::{
+        LocalPlayer, Player, PlayerInterpolation, PositionUpdatedEvent, interpolate_players,
+        update_player_position,
+    },
+    prediction::{MovementStep, PredictionConfig, direction_delta},
+};
+use crate::AppSystems;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RollbackConfig>();
+    app.add_systems(
+        Update,
+        (
+            start_input_buffer,
+            record_local_inputs.in_set(AppSystems::Update),
+            reconcile_rollback
+                .after(update_player_position)
+                .before(interpolate_players),
+        )
+            .chain()
+            .run_if(|config: Res<RollbackConfig>| config.enabled),
+    );
+    app.add_systems(
+        Update,
+        warn_prediction_and_rollback.run_if(
+            |rollback: Res<RollbackConfig>, prediction: Res<PredictionConfig>| {
+                (rollback.is_changed() || prediction.is_changed())
+                    && rollback.enabled
+                    && prediction.enabled
+            },
+        ),
+    );
+}
+
+/// Enables the rollback of the local player's moves, in place of [`PredictionConfig`].
+#[derive(Resource, Debug)]
+pub struct RollbackConfig {
+    pub enabled: bool,
+    /// Moves kept at most; the oldest are considered applied beyond it.
+    pub buffer_len: usize,
+    /// Moves older than this are dropped when Torii doesn't confirm them, e.g. when the
+    /// transaction failed or the move was a no-op onchain.
+    pub max_age: Duration,
+}
+
+impl Default for RollbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            buffer_len: 32,
+            max_age: Duration::from_secs(3),
+        }
+    }
+}
+
+/// A move applied locally, not confirmed by Torii yet.
+#[derive(Debug, Clone, Copy)]
+pub struct TimedInput {
+    pub direction: u8,
+    /// Elapsed app time of the move.
+    pub at: Duration,
+    /// The cell the player is expected to end up at, this move and the previous ones applied.
+    pub cell: IVec3,
+}
+
+/// The moves of the [`LocalPlayer`] applied on top of the last authoritative position.
+#[derive(Component, Debug, Default)]
+pub struct InputBuffer {
+    /// Last position pushed by Torii, in cells.
+    pub authoritative: IVec3,
+    /// Oldest first.
+    inputs: VecDeque<TimedInput>,
+}
+
+impl InputBuffer {
+    /// The cell the player is expected to end up at.
+    pub fn predicted(&self) -> IVec3 {
+        self.inputs
+            .back()
+            .map_or(self.authoritative, |input| input.cell)
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = &TimedInput> {
+        self.inputs.iter()
+    }
+
+    /// Applies a move on top of the predicted cell.
+    fn push(&mut self, direction: u8, at: Duration, step: MovementStep) {
+        let cell = self.predicted() + direction_cells(direction, step);
+        self.inputs.push_back(TimedInput {
+            direction,
+            at,
+            cell,
+        });
+    }
+
+    /// Drops the moves the `authoritative` position from Torii includes, and applies the
+    /// others on top of it.
+    fn confirm(&mut self, authoritative: IVec3, step: MovementStep) {
+        match self.confirmed_len(authoritative, step) {
+            Some(confirmed) => {
+                self.inputs.drain(..confirmed);
+            }
+            None => {
+                debug!(target: LOG_TARGET, ?authoritative, "Rolling back the local moves");
+            }
+        }
+        self.replay_from(authoritative, step);
+    }
+
+    /// How many of the oldest moves `authoritative` includes: up to the first one ending there,
+    /// or, if Torii rejected one of them, up to the first one ending there without it.
+    ///
+    /// The first match is kept when the player came back to an earlier cell, the later moves
+    /// being confirmed by their own updates.
+    fn confirmed_len(&self, authoritative: IVec3, step: MovementStep) -> Option<usize> {
+        if let Some(index) = self
+            .inputs
+            .iter()
+            .position(|input| input.cell == authoritative)
+        {
+            return Some(index + 1);
+        }
+        // Without the rejected move, the cells after it are shifted back by its direction.
+        (0..self.inputs.len()).find_map(|rejected| {
+            let shift = direction_cells(self.inputs[rejected].direction, step);
+            self.inputs
+                .iter()
+                .skip(rejected + 1)
+                .position(|input| input.cell - shift == authoritative)
+                .map(|index| rejected + index + 2)
+        })
+    }
+
+    /// Drops the moves older than `max_age` Torii never confirmed, applying the later ones on
+    /// the authoritative cell. Returns how many were dropped.
+    fn expire(&mut self, now: Duration, max_age: Duration, step: MovementStep) -> usize {
+        let expired = self
+            .inputs
+            .iter()
+            .take_while(|input| now.saturating_sub(input.at) > max_age)
+            .count();
+        if expired > 0 {
+            self.inputs.drain(..expired);
+            self.replay_from(self.authoritative, step);
+        }
+        expired
+    }
+
+    /// Rewinds to `authoritative` and applies the remaining moves again, from the oldest.
+    fn replay_from(&mut self, authoritative: IVec3, step: MovementStep) {
+        self.authoritative = authoritative;
+        let mut cell = authoritative;
+        for input in &mut self.inputs {
+            cell += direction_cells(input.direction, step);
+            input.cell = cell;
+        }
+    }
+}
+
+fn direction_cells(direction: u8, MovementStep(step): MovementStep) -> IVec3 {
+    direction_delta(direction).map_or(IVec3::ZERO, |delta| delta.as_ivec3() * step as i32)
+}
+
+fn warn_prediction_and_rollback() {
+    warn!(
+        target: LOG_TARGET,
+        "Both the prediction and the rollback are enabled, disable the prediction"
+    );
+}
+
+fn start_input_buffer(
+    mut commands: Commands,
+    query: Query<(Entity, &GridPosition), Added<LocalPlayer>>,
+) {
+    for (entity, GridPosition(cell)) in &query {
+        commands.entity(entity).insert(InputBuffer {
+            authoritative: *cell,
+            ..default()
+        });
+    }
+}
+
+fn record_local_inputs(
+    time: Res<Time>,
+    config: Res<RollbackConfig>,
+    scale: Res<CoordinateScale>,
+    step: Res<MovementStep>,
+    mut ev_move_submitted: EventReader<MoveSubmitted>,
+    mut local_player: Query<(&mut PlayerInterpolation, &mut InputBuffer), With<LocalPlayer>>,
+) {
+    let Ok((mut interpolation, mut buffer)) = local_player.single_mut() else {
+        ev_move_submitted.clear();
+        return;
+    };
+
+    for MoveSubmitted { direction } in ev_move_submitted.read().copied() {
+        buffer.push(direction, time.elapsed(), *step);
+        interpolation.target = scale.to_world(buffer.predicted().as_vec3());
+    }
+    while buffer.inputs.len() > config.buffer_len {
+        let applied = buffer.inputs.pop_front();
+        if let Some(applied) = applied {
+            buffer.authoritative = applied.cell;
+        }
+    }
+}
+
+/// Runs after `update_player_position`, which set the interpolation target to the position
+/// from Torii, and puts back the target with the unconfirmed moves applied.
+fn reconcile_rollback(
+    time: Res<Time>,
+    config: Res<RollbackConfig>,
+    scale: Res<CoordinateScale>,
+    step: Res<MovementStep>,
+    mut ev_position_updated: EventReader<PositionUpdatedEvent>,
+    mut local_player: Query<
+        (&Player, &mut PlayerInterpolation, &mut InputBuffer),
+        With<LocalPlayer>,
+    >,
+) {
+    let Ok((player, mut interpolation, mut buffer)) = local_player.single_mut() else {
+        ev_position_updated.clear();
+        return;
+    };
+
+    let mut reconciled = false;
+    for ev in ev_position_updated.read() {
+        if ev.position.player != player.id {
+            continue;
+        }
+        let authoritative = ev.position.as_ivec3();
+        // An update that doesn't move the player, e.g. for another model field.
+        if authoritative == buffer.authoritative {
+            continue;
+        }
+
+        buffer.confirm(authoritative, *step);
+        reconciled = true;
+    }
+
+    let expired = buffer.expire(time.elapsed(), config.max_age, *step);
+    if expired > 0 {
+        debug!(target: LOG_TARGET, expired, "Dropping unconfirmed local moves");
+        reconciled = true;
+    }
+
+    if reconciled || !buffer.inputs.is_empty() {
+        interpolation.target = scale.to_world(buffer.predicted().as_vec3());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: u8 = 0;
+    const RIGHT: u8 = 1;
+    const UP: u8 = 3;
+    const STEP: MovementStep = MovementStep(1);
+
+    fn buffer(moves: &[u8]) -> InputBuffer {
+        let mut buffer = InputBuffer {
+            authoritative: IVec3::new(10, 10, 0),
+            ..default()
+        };
+        for (i, direction) in moves.iter().enumerate() {
+            buffer.push(*direction, Duration::from_millis(100 * i as u64), STEP);
+        }
+        buffer
+    }
+
+    fn cells(buffer: &InputBuffer) -> Vec<IVec3> {
+        buffer.inputs().map(|input| input.cell).collect()
+    }
+
+    #[test]
+    fn moves_confirmed_in_order_are_dropped() {
+        let mut buffer = buffer(&[RIGHT, RIGHT, UP]);
+
+        buffer.confirm(IVec3::new(11, 10, 0), STEP);
+        assert_eq!(
+            cells(&buffer),
+            [IVec3::new(12, 10, 0), IVec3::new(12, 11, 0)]
+        );
+        buffer.confirm(IVec3::new(12, 10, 0), STEP);
+        buffer.confirm(IVec3::new(12, 11, 0), STEP);
+        assert_eq!(buffer.inputs().count(), 0);
+        assert_eq!(buffer.predicted(), IVec3::new(12, 11, 0));
+    }
+
+    #[test]
+    fn a_rejected_move_only_drops_itself() {
+        let mut buffer = buffer(&[RIGHT, UP, RIGHT]);
+
+        // The `UP` is rejected, the second `RIGHT` lands next to the first one.
+        buffer.confirm(IVec3::new(11, 10, 0), STEP);
+        buffer.confirm(IVec3::new(12, 10, 0), STEP);
+        assert_eq!(buffer.inputs().count(), 0);
+        assert_eq!(buffer.predicted(), IVec3::new(12, 10, 0));
+    }
+
+    #[test]
+    fn coming_back_to_a_cell_only_confirms_the_first_move_there() {
+        let mut buffer = buffer(&[RIGHT, LEFT, RIGHT]);
+
+        buffer.confirm(IVec3::new(11, 10, 0), STEP);
+        assert_eq!(
+            cells(&buffer),
+            [IVec3::new(10, 10, 0), IVec3::new(11, 10, 0)]
+        );
+        buffer.confirm(IVec3::new(10, 10, 0), STEP);
+        assert_eq!(cells(&buffer), [IVec3::new(11, 10, 0)]);
+        buffer.confirm(IVec3::new(11, 10, 0), STEP);
+        assert_eq!(buffer.inputs().count(), 0);
+    }
+
+    #[test]
+    fn unconfirmed_moves_expire() {
+        let mut buffer = buffer(&[RIGHT, UP]);
+        let max_age = Duration::from_secs(3);
+
+        assert_eq!(buffer.expire(Duration::from_secs(3), max_age, STEP), 0);
+        // Only the first move is older than `max_age`, the second one is applied without it.
+        assert_eq!(buffer.expire(Duration::from_millis(3050), max_age, STEP), 1);
+        assert_eq!(cells(&buffer), [IVec3::new(10, 11, 0)]);
+    }
+}
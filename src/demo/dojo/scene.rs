@@ -0,0 +1,118 @@
+//! A default 3D scene for the players: a camera, a light and a ground under the grid.
+
+use bevy::prelude::*;
+
+use super::{appearance::PlayerAppearance, coordinates::CoordinateScale, grid::GridConfig};
+use crate::screens::Screen;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SceneBootstrap>();
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        spawn_scene.run_if(|bootstrap: Res<SceneBootstrap>| bootstrap.enabled),
+    );
+    app.add_systems(
+        Update,
+        fit_ground.run_if(
+            resource_changed::<CoordinateScale>
+                .or(resource_changed::<GridConfig>)
+                .or(resource_changed::<PlayerAppearance>)
+                .or(any_match_filter::<Added<SceneGround>>),
+        ),
+    );
+}
+
+/// Controls the default scene, spawned when entering [`Screen::Gameplay`] and despawned when
+/// leaving it, so the menus are drawn alone.
+///
+/// Without a 3D camera the players aren't rendered at all, so it's enabled by default. Games
+/// providing their own camera and lights disable it before entering the gameplay:
+///
+/// ```ignore
+/// app.insert_resource(SceneBootstrap { enabled: false, ..default() });
+/// ```
+#[derive(Resource, Debug)]
+pub struct SceneBootstrap {
+    pub enabled: bool,
+    /// Distance between the camera and the `z = 0` plane, in world units.
+    pub camera_distance: f32,
+    pub ground_color: Color,
+}
+
+impl Default for SceneBootstrap {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            camera_distance: 15.0,
+            ground_color: Color::srgb(0.2, 0.22, 0.25),
+        }
+    }
+}
+
+/// The ground spawned by [`SceneBootstrap`], resized to cover the [`GridConfig`].
+#[derive(Component, Debug)]
+pub struct SceneGround;
+
+fn spawn_scene(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bootstrap: Res<SceneBootstrap>,
+    scale: Res<CoordinateScale>,
+) {
+    let center = scale.origin_offset.extend(0.0);
+    commands.spawn((
+        Name::new("Dojo Camera"),
+        StateScoped(Screen::Gameplay),
+        Camera3d::default(),
+        // Drawn over the 2D camera, which clears the screen. Being the last camera, it also
+        // renders the UI.
+        Camera {
+            order: 1,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        Transform::from_translation(center + Vec3::Z * bootstrap.camera_distance)
+            .looking_at(center, Vec3::Y),
+    ));
+
+    commands.spawn((
+        Name::new("Dojo Light"),
+        StateScoped(Screen::Gameplay),
+        DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(4.0, -8.0, 12.0).looking_at(Vec3::ZERO, Vec3::Z),
+    ));
+
+    // A unit square, scaled by `fit_ground`.
+    commands.spawn((
+        Name::new("Dojo Ground"),
+        StateScoped(Screen::Gameplay),
+        SceneGround,
+        Mesh3d(meshes.add(Plane3d::new(Vec3::Z, Vec2::splat(0.5)))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: bootstrap.ground_color,
+            perceptual_roughness: 1.0,
+            ..default()
+        })),
+        Transform::default(),
+    ));
+}
+
+/// Keeps the ground under the grid, just below the players resting on `z = 0`.
+fn fit_ground(
+    grid: Res<GridConfig>,
+    scale: Res<CoordinateScale>,
+    appearance: Res<PlayerAppearance>,
+    mut ground: Query<&mut Transform, With<SceneGround>>,
+) {
+    // The grid covers `size` cells on each side of the origin.
+    let extent = 2.0 * grid.size as f32 * scale.units_per_cell;
+    let depth = -appearance.size / 2.0 - 0.01;
+    for mut transform in &mut ground {
+        transform.translation = scale.origin_offset.extend(depth);
+        transform.scale = Vec3::new(extent, extent, 1.0);
+    }
+}
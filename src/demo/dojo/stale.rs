@@ -0,0 +1,84 @@
+//! Dims the players that stopped moving, e.g. because their client crashed.
+//!
+//! Torii only sends a position when it changes, so a player that left without despawning
+//! stays at its last cell. Marking it [`Stale`] tells it apart from the active players, until
+//! its next update.
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+use super::{
+    LOG_TARGET,
+    animation::DespawnAnimation,
+    appearance::PlayerAppearance,
+    intro::{LocalPlayer, PendingSpawn, Player, update_player_position},
+    persistence::Restored,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<StaleThreshold>();
+    app.add_systems(Update, mark_stale_players.after(update_player_position));
+}
+
+/// How much darker the stale players are.
+const STALE_DIMMING: f32 = 0.5;
+
+/// A player whose last update is older than the [`StaleThreshold`] is marked [`Stale`].
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct StaleThreshold(pub Duration);
+
+impl Default for StaleThreshold {
+    fn default() -> Self {
+        Self(Duration::from_secs(60))
+    }
+}
+
+/// A [`Player`] without any update for longer than the [`StaleThreshold`].
+///
+/// The local player is never marked, and the players restored from the previous session are
+/// already dimmed until Torii confirms them.
+#[derive(Component, Debug)]
+pub struct Stale;
+
+fn mark_stale_players(
+    mut commands: Commands,
+    time: Res<Time>,
+    threshold: Res<StaleThreshold>,
+    appearance: Res<PlayerAppearance>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    players: Query<
+        (
+            Entity,
+            &Player,
+            &MeshMaterial3d<StandardMaterial>,
+            Has<Stale>,
+        ),
+        (
+            Without<LocalPlayer>,
+            Without<PendingSpawn>,
+            Without<Restored>,
+            Without<DespawnAnimation>,
+        ),
+    >,
+) {
+    let now = time.elapsed();
+    for (entity, player, material, was_stale) in &players {
+        let is_stale = now.saturating_sub(player.last_update) > threshold.0;
+        if is_stale == was_stale {
+            continue;
+        }
+
+        let Some(material) = materials.get_mut(&material.0) else {
+            continue;
+        };
+        if is_stale {
+            debug!(target: LOG_TARGET, player = ?player.id, "Player is stale");
+            material.base_color = material.base_color.darker(STALE_DIMMING);
+            commands.entity(entity).insert(Stale);
+        } else {
+            debug!(target: LOG_TARGET, player = ?player.id, "Player is active again");
+            material.base_color = appearance.material(player.id).base_color;
+            commands.entity(entity).remove::<Stale>();
+        }
+    }
+}
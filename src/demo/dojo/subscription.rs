@@ -0,0 +1,286 @@
+//! Live Torii updates for several models through a single subscription.
+
+use bevy::prelude::*;
+use dojo_bevy_plugin::{DojoInitializedEvent, DojoResource, TokioRuntime};
+use starknet::core::types::Felt;
+use std::time::Duration;
+use torii_grpc_client::types::Clause;
+
+use super::{
+    DojoDisconnected, DojoSystemState, LOG_TARGET,
+    error::{DojoError, DojoErrorEvent},
+    query::{and, keys_clause},
+    registry::ModelRegistry,
+};
+use crate::constants::dojo::DojoConfig;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SubscribedModels>();
+    app.init_resource::<SubscriptionClause>();
+    app.init_resource::<ActiveSubscription>();
+    app.add_event::<SubscribeRequest>();
+    app.add_event::<SubscriptionReady>();
+    app.add_systems(
+        Update,
+        (
+            forget_subscription.run_if(on_event::<DojoDisconnected>),
+            resubscribe_on_clause_change.run_if(resource_changed::<SubscriptionClause>),
+            request_auto_subscription
+                .run_if(on_event::<DojoInitializedEvent>)
+                .run_if(resource_exists::<AutoSubscribe>),
+            subscribe_to_models
+                .run_if(on_event::<SubscribeRequest>)
+                .run_if(resource_exists::<TokioRuntime>),
+            watch_pending_subscription.run_if(resource_exists::<PendingSubscription>),
+        )
+            .chain(),
+    );
+}
+
+/// The models to receive live updates for. Defaults to `position`.
+///
+/// A bare name (e.g. `position`) is matched against the registered models of the configured
+/// namespace regardless of case. A name with its namespace (e.g. `di-Position`) is moved to the
+/// one of the config like the registered models.
+///
+/// A name missing from the [`ModelRegistry`], e.g. with the wrong namespace, is still
+/// subscribed to but warned about, since its updates are ignored.
+///
+/// Empty subscribes to every model registered in the [`ModelRegistry`] instead.
+#[derive(Resource, Debug, Clone)]
+pub struct SubscribedModels(pub Vec<String>);
+
+impl Default for SubscribedModels {
+    fn default() -> Self {
+        Self(vec!["position".to_string()])
+    }
+}
+
+/// Narrows the subscription down, e.g. to the positions around the local player.
+///
+/// `None` receives every entity of the [`SubscribedModels`]. Changing it while subscribed
+/// subscribes again with the new clause, so it can follow the local player as it moves.
+///
+/// ```ignore
+/// let clause = and([
+///     member_clause("di-Position", "x", ComparisonOperator::Gte, Primitive::U32(Some(0))),
+///     member_clause("di-Position", "x", ComparisonOperator::Lte, Primitive::U32(Some(20))),
+/// ]);
+/// commands.insert_resource(SubscriptionClause(Some(clause)));
+/// ```
+#[derive(Resource, Debug, Default, Clone)]
+pub struct SubscriptionClause(pub Option<Clause>);
+
+/// Requests a subscription covering the [`SubscribedModels`].
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct SubscribeRequest;
+
+/// Subscribes to the [`SubscribedModels`] whenever Torii is connected, e.g. again after
+/// switching worlds. Inserted by [`DojoPlugin::auto_subscribe`].
+///
+/// [`DojoPlugin::auto_subscribe`]: super::DojoPlugin::auto_subscribe
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct AutoSubscribe;
+
+/// Emitted when Torii confirms a subscription, before any update it streams.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SubscriptionReady;
+
+/// Whether `entity_id` is the one Torii sends, once, when a subscription is initialized.
+///
+/// It doesn't identify any entity, and is reported as [`SubscriptionReady`] instead.
+pub fn is_subscription_sentinel(entity_id: Felt) -> bool {
+    entity_id == Felt::ZERO
+}
+
+/// What the current subscription covers, to avoid subscribing twice.
+#[derive(Resource, Debug, Default)]
+struct ActiveSubscription(Option<Subscription>);
+
+/// A subscription Torii didn't confirm yet with a [`SubscriptionReady`].
+#[derive(Resource, Debug)]
+struct PendingSubscription {
+    /// Elapsed app time when the subscription was requested.
+    started_at: Duration,
+    /// See [`DojoConfig::request_timeout`](crate::constants::dojo::DojoConfig::request_timeout).
+    timeout: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+struct Subscription {
+    models: Vec<String>,
+    clause: Option<Clause>,
+}
+
+pub trait DojoResourceExt {
+    /// Subscribes to the entities holding any of `models`, keyed by any value, and matching
+    /// `clause` if any.
+    fn subscribe_models(&mut self, tokio: &TokioRuntime, models: &[&str], clause: Option<Clause>);
+}
+
+impl DojoResourceExt for DojoResource {
+    fn subscribe_models(&mut self, tokio: &TokioRuntime, models: &[&str], clause: Option<Clause>) {
+        // The subscription id is derived from the models, so one set never gets two streams:
+        // subscribing with another clause replaces the previous one.
+        let id = models.join(",");
+        let keys = keys_clause(vec![None], models.iter().copied());
+        let clause = match clause {
+            Some(clause) => and([keys, clause]),
+            None => keys,
+        };
+        self.subscribe_entities(tokio, id, Some(clause));
+    }
+}
+
+fn subscribe_to_models(
+    mut commands: Commands,
+    time: Res<Time>,
+    dojo_state: Res<DojoSystemState>,
+    tokio: Res<TokioRuntime>,
+    mut dojo: ResMut<DojoResource>,
+    subscribed: Res<SubscribedModels>,
+    clause: Res<SubscriptionClause>,
+    registry: Res<ModelRegistry>,
+    mut active: ResMut<ActiveSubscription>,
+    mut ev_subscribe: EventReader<SubscribeRequest>,
+) {
+    ev_subscribe.clear();
+
+    let mut models: Vec<String> = if subscribed.0.is_empty() {
+        registry.model_names().map(str::to_string).collect()
+    } else {
+        subscribed
+            .0
+            .iter()
+            .map(|model| subscribed_model_name(model, &dojo_state.config, &registry))
+            .collect()
+    };
+    models.sort_unstable();
+    models.dedup();
+    for model in models.iter().filter(|model| registry.get(model).is_none()) {
+        warn!(
+            target: LOG_TARGET,
+            %model,
+            "Subscribing to a model missing from the registry, its updates are ignored"
+        );
+    }
+
+    let subscription = Subscription {
+        models: models.clone(),
+        clause: clause.0.clone(),
+    };
+    if active.0.as_ref() == Some(&subscription) {
+        info!(target: LOG_TARGET, "Already subscribed to {:?}", models);
+        return;
+    }
+
+    let _span = info_span!(
+        target: LOG_TARGET,
+        "torii_subscribe",
+        models = ?models,
+        filtered = subscription.clause.is_some()
+    )
+    .entered();
+    info!(target: LOG_TARGET, "Setting up Torii subscription to {:?}", models);
+    let names: Vec<&str> = models.iter().map(String::as_str).collect();
+    dojo.subscribe_models(&tokio, &names, subscription.clause.clone());
+    active.0 = Some(subscription);
+    commands.insert_resource(PendingSubscription {
+        started_at: time.elapsed(),
+        timeout: dojo_state.config.request_timeout,
+    });
+}
+
+/// The name Torii knows one of the [`SubscribedModels`] by.
+fn subscribed_model_name(model: &str, config: &DojoConfig, registry: &ModelRegistry) -> String {
+    if model.contains('-') {
+        return config.model_name(model);
+    }
+    registry
+        .model_names()
+        .find(|name| {
+            name.split_once('-').is_some_and(|(namespace, name)| {
+                namespace == config.namespace && name.eq_ignore_ascii_case(model)
+            })
+        })
+        .map_or_else(|| format!("{}-{model}", config.namespace), str::to_string)
+}
+
+/// Forgets a subscription Torii never confirmed, so the next request subscribes again.
+fn watch_pending_subscription(
+    mut commands: Commands,
+    time: Res<Time>,
+    pending: Res<PendingSubscription>,
+    mut active: ResMut<ActiveSubscription>,
+    mut ev_ready: EventReader<SubscriptionReady>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    if ev_ready.read().count() > 0 {
+        commands.remove_resource::<PendingSubscription>();
+    } else if time.elapsed().saturating_sub(pending.started_at) >= pending.timeout {
+        warn!(target: LOG_TARGET, timeout = ?pending.timeout, "Torii subscription timed out");
+        ev_error.write(
+            DojoError::ConnectionFailed(format!(
+                "Torii subscription timed out after {:?}",
+                pending.timeout
+            ))
+            .into(),
+        );
+        active.0 = None;
+        commands.remove_resource::<PendingSubscription>();
+    }
+}
+
+/// Only resubscribes when already subscribed, the clause is picked up by the next request
+/// otherwise.
+fn resubscribe_on_clause_change(
+    active: Res<ActiveSubscription>,
+    mut ev_subscribe: EventWriter<SubscribeRequest>,
+) {
+    if active.0.is_some() {
+        ev_subscribe.write(SubscribeRequest);
+    }
+}
+
+fn forget_subscription(mut commands: Commands, mut active: ResMut<ActiveSubscription>) {
+    active.0 = None;
+    commands.remove_resource::<PendingSubscription>();
+}
+
+fn request_auto_subscription(
+    mut ev_initialized: EventReader<DojoInitializedEvent>,
+    mut ev_subscribe: EventWriter<SubscribeRequest>,
+) {
+    ev_initialized.clear();
+    ev_subscribe.write(SubscribeRequest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo::dojo::{intro::Position, registry::DojoModel};
+
+    #[test]
+    fn subscribed_models_are_resolved_in_the_namespace() {
+        let config = DojoConfig {
+            namespace: "my_game".to_string(),
+            ..default()
+        };
+        let mut registry = ModelRegistry::default();
+        registry.set_namespace(&config.namespace);
+        registry.register(Position::NAME, |_, _, _| Ok(()));
+
+        for (model, name) in [
+            ("position", "my_game-Position"),
+            ("di-Position", "my_game-Position"),
+            ("moves", "my_game-moves"),
+            ("other-Moves", "other-Moves"),
+        ] {
+            assert_eq!(
+                subscribed_model_name(model, &config, &registry),
+                name,
+                "{model}"
+            );
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Initial sync of the world, fetched page by page from Torii.
+//!
+//! `DojoResource::queue_retrieve_entities` only forwards the entities and drops the cursor of
+//! the response, so the pages are fetched with a dedicated Torii client instead. The entities
+//! are re-emitted as [`DojoEntityUpdated`], like the ones coming from the plugin.
+
+use bevy::{log::tracing::Instrument, prelude::*};
+use dojo_bevy_plugin::{DojoEntityUpdated, TokioRuntime};
+use starknet::core::types::Felt;
+use std::{sync::Mutex, time::Duration};
+use tokio::sync::mpsc::{Receiver, Sender, channel};
+use torii_grpc_client::{
+    WorldClient,
+    types::{OrderBy, OrderDirection, schema::Entity},
+};
+
+use super::{
+    DojoSystemState, LOG_TARGET,
+    error::{DojoError, DojoErrorEvent},
+    intro::Position,
+    query::DojoQueryBuilder,
+    registry::DojoModel,
+    task::{self, TaskHandle},
+    throttle::{DeferredUpdates, MaxUpdatesPerFrame},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SyncOrder>();
+    app.add_event::<InitialSyncComplete>();
+    app.add_systems(
+        PreUpdate,
+        drain_initial_sync.run_if(resource_exists::<InitialSync>),
+    );
+}
+
+/// Emitted once every page of the initial fetch was received.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InitialSyncComplete {
+    pub entities: usize,
+    pub pages: u32,
+}
+
+/// The order in which the initial sync fetches the entities, and so spawns the players.
+///
+/// Defaults to the player address, ascending, so every launch spawns the players in the same
+/// order, e.g. for reproducible screenshots. Empty leaves the order to Torii.
+#[derive(Resource, Debug, Clone)]
+pub struct SyncOrder(pub Vec<OrderBy>);
+
+impl Default for SyncOrder {
+    fn default() -> Self {
+        Self(vec![OrderBy {
+            model: Position::NAME.to_string(),
+            member: "player".to_string(),
+            direction: OrderDirection::Asc,
+        }])
+    }
+}
+
+enum SyncMessage {
+    Entity(DojoEntityUpdated),
+    Complete { entities: usize, pages: u32 },
+    Failed(String),
+}
+
+/// The initial fetch in progress. Dropping it aborts the fetch.
+#[derive(Resource)]
+pub struct InitialSync {
+    receiver: Mutex<Receiver<SyncMessage>>,
+    task: TaskHandle,
+}
+
+impl Drop for InitialSync {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Starts fetching every entity holding one of `models`, `query_limit` entities at a time.
+///
+/// At most `capacity` messages are buffered, the fetch waiting for the game to drain them
+/// before requesting the next pages.
+pub fn start_initial_sync<I, S>(
+    commands: &mut Commands,
+    tokio: &TokioRuntime,
+    dojo_state: &DojoSystemState,
+    capacity: usize,
+    order: &SyncOrder,
+    models: I,
+) where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let (sender, receiver) = channel(capacity.max(1));
+    let torii_url = dojo_state.config.torii_url.clone();
+    let world_address = dojo_state.config.world_address;
+    let limit = dojo_state.config.query_limit;
+    let timeout = dojo_state.config.request_timeout;
+    let models: Vec<String> = models.into_iter().map(Into::into).collect();
+    let mut order = order.0.clone();
+    for order_by in &mut order {
+        order_by.model = dojo_state.config.model_name(&order_by.model);
+    }
+
+    let span = info_span!(
+        target: LOG_TARGET,
+        "torii_query",
+        url = %torii_url,
+        models = models.len(),
+        limit
+    );
+    let task = task::spawn(
+        tokio,
+        async move {
+            let result = fetch_pages(
+                torii_url,
+                world_address,
+                limit,
+                timeout,
+                models,
+                order,
+                &sender,
+            )
+            .await;
+            if let Err(error) = result {
+                warn!(target: LOG_TARGET, %error, "Initial sync failed");
+                let _ = sender.send(SyncMessage::Failed(error)).await;
+            }
+        }
+        .instrument(span),
+    );
+
+    // Replacing a previous sync aborts it.
+    commands.insert_resource(InitialSync {
+        receiver: Mutex::new(receiver),
+        task,
+    });
+}
+
+async fn fetch_pages(
+    torii_url: String,
+    world_address: Felt,
+    limit: u32,
+    timeout: Duration,
+    models: Vec<String>,
+    order: Vec<OrderBy>,
+    sender: &Sender<SyncMessage>,
+) -> Result<(), String> {
+    let mut client = task::timeout(timeout, WorldClient::new(torii_url, world_address))
+        .await?
+        .map_err(|error| error.to_string())?;
+
+    let mut cursor: Option<String> = None;
+    let mut entities = 0;
+    let mut pages = 0;
+    loop {
+        let mut query = DojoQueryBuilder::new()
+            .limit(limit)
+            .keys(vec![None], models.iter().cloned());
+        if let Some(cursor) = cursor.take() {
+            query = query.cursor(cursor);
+        }
+        for OrderBy {
+            model,
+            member,
+            direction,
+        } in order.iter().cloned()
+        {
+            query = query.order_by(model, member, direction);
+        }
+
+        let response = task::timeout(timeout, client.retrieve_entities(query.build()))
+            .await?
+            .map_err(|error| error.to_string())?;
+        pages += 1;
+
+        let page_len = response.entities.len();
+        debug!(target: LOG_TARGET, page = pages, entities = page_len, "Received page");
+        for entity in response.entities {
+            let entity = Entity::try_from(entity).map_err(|error| error.to_string())?;
+            entities += 1;
+            let update = DojoEntityUpdated {
+                entity_id: entity.hashed_keys,
+                models: entity.models,
+            };
+            // Closed once the sync is dropped, which aborts this task anyway.
+            if sender.send(SyncMessage::Entity(update)).await.is_err() {
+                return Ok(());
+            }
+        }
+
+        // A partial page, or no cursor, means the world is fully loaded.
+        if page_len < limit as usize || response.next_cursor.is_empty() {
+            break;
+        }
+        cursor = Some(response.next_cursor);
+    }
+
+    let _ = sender.send(SyncMessage::Complete { entities, pages }).await;
+    Ok(())
+}
+
+/// Takes the messages of the sync, no more than the updates [`MaxUpdatesPerFrame`] leaves
+/// room for, so a slow frame slows the fetch down instead of piling updates up.
+fn drain_initial_sync(
+    mut commands: Commands,
+    sync: Res<InitialSync>,
+    max_updates: Res<MaxUpdatesPerFrame>,
+    deferred: Res<DeferredUpdates>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+    mut ev_entity_updated: EventWriter<DojoEntityUpdated>,
+    mut ev_sync_complete: EventWriter<InitialSyncComplete>,
+) {
+    let budget = max_updates.0.max(1).saturating_sub(deferred.len());
+    let messages: Vec<SyncMessage> = sync
+        .receiver
+        .lock()
+        .map(|mut receiver| {
+            std::iter::from_fn(|| receiver.try_recv().ok())
+                .take(budget)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for message in messages {
+        match message {
+            SyncMessage::Entity(update) => {
+                ev_entity_updated.write(update);
+            }
+            SyncMessage::Complete { entities, pages } => {
+                info!(target: LOG_TARGET, entities, pages, "Initial sync complete");
+                ev_sync_complete.write(InitialSyncComplete { entities, pages });
+                commands.remove_resource::<InitialSync>();
+            }
+            SyncMessage::Failed(error) => {
+                ev_error.write(
+                    DojoError::ConnectionFailed(format!("initial sync failed: {error}")).into(),
+                );
+                commands.remove_resource::<InitialSync>();
+            }
+        }
+    }
+}
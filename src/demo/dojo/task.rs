@@ -0,0 +1,109 @@
+//! Runs the async work of the Dojo systems, wherever the game runs.
+//!
+//! Natively, tasks are spawned on the [`TokioRuntime`]. With the `wasm` feature they run on the
+//! browser's event loop through `wasm-bindgen-futures` instead, the runtime being ignored, so
+//! the systems keep the same signatures on every platform.
+
+use dojo_bevy_plugin::TokioRuntime;
+use std::time::Duration;
+
+/// A future the Dojo systems can spawn. Browser futures aren't `Send`, so only native ones
+/// have to be.
+#[cfg(not(feature = "wasm"))]
+pub trait TaskFuture: Future<Output = ()> + Send + 'static {}
+#[cfg(not(feature = "wasm"))]
+impl<F: Future<Output = ()> + Send + 'static> TaskFuture for F {}
+
+/// A future the Dojo systems can spawn. Browser futures aren't `Send`, so only native ones
+/// have to be.
+#[cfg(feature = "wasm")]
+pub trait TaskFuture: Future<Output = ()> + 'static {}
+#[cfg(feature = "wasm")]
+impl<F: Future<Output = ()> + 'static> TaskFuture for F {}
+
+/// Lets a spawned task be aborted. Dropping it leaves the task running.
+#[cfg(not(feature = "wasm"))]
+#[derive(Debug)]
+pub struct TaskHandle(tokio::task::AbortHandle);
+
+#[cfg(not(feature = "wasm"))]
+impl TaskHandle {
+    pub fn abort(&self) {
+        self.0.abort();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+}
+
+/// Spawns `future` on the Tokio runtime.
+#[cfg(not(feature = "wasm"))]
+pub fn spawn(tokio: &TokioRuntime, future: impl TaskFuture) -> TaskHandle {
+    TaskHandle(tokio.runtime.spawn(future).abort_handle())
+}
+
+#[cfg(not(feature = "wasm"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Runs `future`, failing once `duration` elapsed before it completed.
+#[cfg(not(feature = "wasm"))]
+pub async fn timeout<T>(duration: Duration, future: impl Future<Output = T>) -> Result<T, String> {
+    tokio::time::timeout(duration, future)
+        .await
+        .map_err(|_| timed_out(duration))
+}
+
+fn timed_out(duration: Duration) -> String {
+    format!("timed out after {duration:?}")
+}
+
+/// Lets a spawned task be aborted. Dropping it leaves the task running.
+#[cfg(feature = "wasm")]
+#[derive(Debug)]
+pub struct TaskHandle {
+    abort: futures_util::future::AbortHandle,
+    finished: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(feature = "wasm")]
+impl TaskHandle {
+    pub fn abort(&self) {
+        self.abort.abort();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.abort.is_aborted() || self.finished.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// Spawns `future` on the browser's event loop. The runtime isn't used.
+#[cfg(feature = "wasm")]
+pub fn spawn(_tokio: &TokioRuntime, future: impl TaskFuture) -> TaskHandle {
+    let (future, abort) = futures_util::future::abortable(future);
+    let finished = std::sync::Arc::default();
+    let task_finished = std::sync::Arc::clone(&finished);
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = future.await;
+        task_finished.store(true, std::sync::atomic::Ordering::Relaxed);
+    });
+    TaskHandle { abort, finished }
+}
+
+#[cfg(feature = "wasm")]
+pub async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await;
+}
+
+/// Runs `future`, failing once `duration` elapsed before it completed.
+#[cfg(feature = "wasm")]
+pub async fn timeout<T>(duration: Duration, future: impl Future<Output = T>) -> Result<T, String> {
+    use futures_util::future::{Either, select};
+
+    match select(std::pin::pin!(future), std::pin::pin!(sleep(duration))).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(timed_out(duration)),
+    }
+}
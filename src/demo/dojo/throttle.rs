@@ -0,0 +1,109 @@
+//! Spreads bursts of Torii updates over several frames, instead of hitching on one.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use dojo_bevy_plugin::DojoEntityUpdated;
+use starknet::core::types::Felt;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<MaxUpdatesPerFrame>();
+    app.init_resource::<UpdateChannelCapacity>();
+    app.init_resource::<DeferredUpdates>();
+}
+
+/// Entity updates applied in a frame, the others waiting for the next frames.
+///
+/// Defaults to 256, a couple of initial sync pages at the default `query_limit` of 100, so a
+/// regular frame is never throttled while a burst of thousands is spread over a few frames.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct MaxUpdatesPerFrame(pub usize);
+
+impl Default for MaxUpdatesPerFrame {
+    fn default() -> Self {
+        Self(256)
+    }
+}
+
+/// Messages buffered between the initial sync task and Bevy.
+///
+/// Once full, the task waits for the game to catch up before fetching more pages. Defaults to
+/// 1024, four frames worth of [`MaxUpdatesPerFrame`].
+///
+/// Only the initial sync is bounded: the live subscription goes through the channel of the
+/// Dojo plugin, which is unbounded. Its updates are read every frame and merged per entity in
+/// the [`DeferredUpdates`], so what waits on the Bevy side is at most one update per entity.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct UpdateChannelCapacity(pub usize);
+
+impl Default for UpdateChannelCapacity {
+    fn default() -> Self {
+        Self(1024)
+    }
+}
+
+/// The updates not applied yet, at most one per entity, carried to the next frames beyond
+/// [`MaxUpdatesPerFrame`]. Oldest first.
+///
+/// Since an entity updated again while waiting is merged into its pending update, a sustained
+/// burst holds as many updates as there are entities, however many Torii sends.
+#[derive(Resource, Default)]
+pub struct DeferredUpdates {
+    updates: Vec<DojoEntityUpdated>,
+    /// Index of the update of each entity in `updates`.
+    by_entity: HashMap<Felt, usize>,
+}
+
+impl DeferredUpdates {
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    /// Drops the carried updates, e.g. when they belong to a world that was just left.
+    pub fn clear(&mut self) {
+        self.updates.clear();
+        self.by_entity.clear();
+    }
+
+    /// Adds an update, merged into the pending one of the same entity, if any, keeping the
+    /// latest value of each model and the order in which the entities were first received.
+    /// Returns whether it was merged; a deletion clears the models merged before it.
+    pub(super) fn push(&mut self, update: &DojoEntityUpdated) -> bool {
+        let Some(&index) = self.by_entity.get(&update.entity_id) else {
+            self.by_entity.insert(update.entity_id, self.updates.len());
+            self.updates.push(DojoEntityUpdated {
+                entity_id: update.entity_id,
+                models: update.models.clone(),
+            });
+            return false;
+        };
+
+        let entry = &mut self.updates[index];
+        // A deletion discards what came before it, a later update recreates the entity.
+        if update.models.is_empty() {
+            entry.models.clear();
+        }
+        for model in &update.models {
+            match entry.models.iter_mut().find(|m| m.name == model.name) {
+                Some(existing) => existing.clone_from(model),
+                None => entry.models.push(model.clone()),
+            }
+        }
+        true
+    }
+
+    /// Removes the `max` oldest updates, to apply them.
+    pub(super) fn drain_front(&mut self, max: usize) -> Vec<DojoEntityUpdated> {
+        let rest = self.updates.split_off(max.min(self.updates.len()));
+        let front = std::mem::replace(&mut self.updates, rest);
+        self.by_entity = self
+            .updates
+            .iter()
+            .enumerate()
+            .map(|(index, update)| (update.entity_id, index))
+            .collect();
+        front
+    }
+}
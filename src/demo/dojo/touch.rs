@@ -0,0 +1,156 @@
+//! On-screen buttons to play without a keyboard, e.g. on mobile.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use super::actions::PlayerAction;
+use crate::{
+    AppSystems,
+    theme::{interaction::InteractionPalette, palette::*},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TouchControls>();
+    app.add_systems(Startup, spawn_touch_controls);
+    app.add_systems(
+        Update,
+        (
+            handle_touch_input.in_set(AppSystems::RecordInput),
+            toggle_touch_controls.run_if(resource_changed::<TouchControls>),
+        ),
+    );
+}
+
+/// Shows the on-screen controls. Enabled by default on web builds.
+#[derive(Resource, Debug)]
+pub struct TouchControls {
+    pub enabled: bool,
+}
+
+// Only derivable on native builds, where it's disabled.
+#[allow(clippy::derivable_impls)]
+impl Default for TouchControls {
+    fn default() -> Self {
+        Self {
+            enabled: cfg!(target_arch = "wasm32"),
+        }
+    }
+}
+
+#[derive(Component)]
+struct TouchControlsRoot;
+
+/// The action sent when the button is pressed.
+#[derive(Component, Debug, Clone, Copy)]
+struct TouchButton(PlayerAction);
+
+const BUTTON_SIZE: f32 = 64.0;
+
+fn spawn_touch_controls(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Touch Controls"),
+        TouchControlsRoot,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Percent(100.0),
+            bottom: Px(20.0),
+            padding: UiRect::horizontal(Px(20.0)),
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::End,
+            ..default()
+        },
+        Visibility::Hidden,
+        GlobalZIndex(1),
+        Pickable::IGNORE,
+        children![
+            (
+                Name::new("Directional Pad"),
+                Node {
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::px(3, BUTTON_SIZE),
+                    grid_template_rows: RepeatedGridTrack::px(3, BUTTON_SIZE),
+                    ..default()
+                },
+                Pickable::IGNORE,
+                // Directions as expected by `move`: left, right, up, down.
+                children![
+                    touch_button("^", PlayerAction::Move(2), 1, 2),
+                    touch_button("<", PlayerAction::Move(0), 2, 1),
+                    touch_button(">", PlayerAction::Move(1), 2, 3),
+                    touch_button("v", PlayerAction::Move(3), 3, 2),
+                ],
+            ),
+            (
+                Name::new("Spawn Button"),
+                Node {
+                    display: Display::Grid,
+                    ..default()
+                },
+                Pickable::IGNORE,
+                children![touch_button("Spawn", PlayerAction::Spawn, 1, 1)],
+            ),
+        ],
+    ));
+}
+
+fn touch_button(text: &str, action: PlayerAction, row: i16, column: i16) -> impl Bundle {
+    (
+        Name::new("Touch Button"),
+        TouchButton(action),
+        Button,
+        Node {
+            grid_row: GridPlacement::start(row),
+            grid_column: GridPlacement::start(column),
+            min_width: Px(BUTTON_SIZE),
+            height: Px(BUTTON_SIZE),
+            padding: UiRect::horizontal(Px(12.0)),
+            margin: UiRect::all(Px(2.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BorderRadius::all(Px(12.0)),
+        BackgroundColor(BUTTON_BACKGROUND),
+        InteractionPalette {
+            none: BUTTON_BACKGROUND,
+            hovered: BUTTON_HOVERED_BACKGROUND,
+            pressed: BUTTON_PRESSED_BACKGROUND,
+        },
+        children![(
+            Name::new("Touch Button Text"),
+            Text(text.to_string()),
+            TextFont::from_font_size(28.0),
+            TextColor(BUTTON_TEXT),
+            Pickable::IGNORE,
+        )],
+    )
+}
+
+/// Sends the action of each button tapped this frame, alongside the keyboard and gamepad.
+fn handle_touch_input(
+    controls: Res<TouchControls>,
+    buttons: Query<(&Interaction, &TouchButton), Changed<Interaction>>,
+    mut ev_player_action: EventWriter<PlayerAction>,
+) {
+    if !controls.enabled {
+        return;
+    }
+
+    for (interaction, button) in &buttons {
+        if *interaction == Interaction::Pressed {
+            ev_player_action.write(button.0);
+        }
+    }
+}
+
+fn toggle_touch_controls(
+    controls: Res<TouchControls>,
+    mut root: Query<&mut Visibility, With<TouchControlsRoot>>,
+) {
+    for mut visibility in &mut root {
+        *visibility = if controls.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
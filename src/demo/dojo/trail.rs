@@ -0,0 +1,103 @@
+//! Lines showing where each player has recently moved, for debugging and analytics.
+
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use super::{
+    appearance::PlayerAppearance,
+    coordinates::CoordinateScale,
+    intro::{Player, PositionUpdatedEvent, update_player_position},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TrailConfig>();
+    app.add_systems(
+        Update,
+        (
+            record_trails
+                .after(update_player_position)
+                .run_if(|config: Res<TrailConfig>| config.enabled),
+            render_trails.run_if(|config: Res<TrailConfig>| config.enabled),
+        ),
+    );
+}
+
+/// Controls the player trails. Disabled by default.
+#[derive(Resource, Debug)]
+pub struct TrailConfig {
+    pub enabled: bool,
+    /// Positions kept per player; the oldest are dropped first.
+    pub max_points: usize,
+}
+
+impl Default for TrailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_points: 32,
+        }
+    }
+}
+
+/// The last positions of a player in cells, oldest first.
+#[derive(Component, Debug, Default)]
+pub struct MovementTrail {
+    points: VecDeque<Vec3>,
+}
+
+impl MovementTrail {
+    /// Appends `point`, dropping the oldest ones beyond `max_points`.
+    pub fn push(&mut self, point: Vec3, max_points: usize) {
+        // Two points in a row at the same place don't draw anything.
+        if self.points.back() == Some(&point) {
+            return;
+        }
+        self.points.push_back(point);
+        while self.points.len() > max_points {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Vec3> + '_ {
+        self.points.iter().copied()
+    }
+}
+
+/// Players get a trail on their first position update after trails are enabled.
+fn record_trails(
+    mut commands: Commands,
+    config: Res<TrailConfig>,
+    mut ev_position_updated: EventReader<PositionUpdatedEvent>,
+    mut players: Query<(Entity, &Player, Option<&mut MovementTrail>)>,
+) {
+    for ev in ev_position_updated.read() {
+        let point = ev.position.as_vec3();
+        for (entity, player, trail) in &mut players {
+            if player.id != ev.position.player {
+                continue;
+            }
+            match trail {
+                Some(mut trail) => trail.push(point, config.max_points),
+                None => {
+                    let mut trail = MovementTrail::default();
+                    trail.push(point, config.max_points);
+                    commands.entity(entity).insert(trail);
+                }
+            }
+        }
+    }
+}
+
+fn render_trails(
+    mut gizmos: Gizmos,
+    scale: Res<CoordinateScale>,
+    appearance: Res<PlayerAppearance>,
+    trails: Query<(&Player, &MovementTrail)>,
+) {
+    for (player, trail) in &trails {
+        gizmos.linestrip(
+            trail.points().map(|point| scale.to_world(point)),
+            appearance.player_color(player.id),
+        );
+    }
+}
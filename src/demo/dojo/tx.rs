@@ -0,0 +1,1001 @@
+//! Submit transactions and follow them until they land onchain.
+//!
+//! [`DojoResource::queue_tx`] is fire-and-forget, so the [`TransactionTracker`] submits calls
+//! itself through a [`TxBackend`]. By default this is [`AccountBackend`], which uses the account
+//! connected by the plugin and polls for the receipt.
+
+use bevy::{
+    log::tracing::Instrument,
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use dojo_bevy_plugin::{DojoResource, TokioRuntime};
+use starknet::{
+    accounts::{Account, ConnectedAccount, ExecutionV3},
+    core::types::{Call, ExecutionResult, FeeEstimate, Felt, StarknetError},
+    providers::{Provider, ProviderError},
+};
+use std::{
+    sync::{
+        Mutex,
+        mpsc::{Receiver, Sender, channel},
+    },
+    time::Duration,
+};
+
+use crate::constants::dojo::DEFAULT_FEE_ESTIMATION_MULTIPLIER;
+
+use super::{
+    DojoSystemState, LOG_TARGET,
+    error::{DojoError, DojoErrorEvent},
+    task::{self, TaskFuture, TaskHandle},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TransactionTracker>();
+    app.init_resource::<DryRun>();
+    app.add_event::<TxStatusChanged>();
+    app.add_event::<TxReverted>();
+    app.add_event::<TxRetried>();
+    app.add_systems(
+        Update,
+        (
+            sync_fee_settings.run_if(resource_changed::<DojoSystemState>),
+            sync_dry_run.run_if(resource_changed::<DryRun>.or(resource_changed::<DojoSystemState>)),
+            (poll_transactions, prune_transactions).chain(),
+        ),
+    );
+}
+
+/// Local identifier of a submitted transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TxId(pub u64);
+
+/// Lifecycle of a submitted transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Sent, or about to be sent, and waiting for a receipt.
+    Pending,
+    /// Included onchain and executed successfully.
+    Confirmed,
+    /// Rejected before inclusion, e.g. because the account isn't connected.
+    Failed(String),
+    /// Reverted by the contract, with the reason parsed by [`parse_revert_reason`].
+    Reverted(String),
+    /// No receipt arrived within [`TransactionTracker::timeout`].
+    TimedOut,
+}
+
+impl TxStatus {
+    pub fn is_final(&self) -> bool {
+        !matches!(self, Self::Pending)
+    }
+}
+
+/// This event will be triggered every time a transaction changes status.
+#[derive(Event, Debug, Clone)]
+pub struct TxStatusChanged {
+    pub id: TxId,
+    pub status: TxStatus,
+}
+
+/// This event will be triggered every time a transaction is reverted by the contract.
+#[derive(Event, Debug, Clone)]
+pub struct TxReverted {
+    pub id: TxId,
+    /// Selector of the first call, the only one for single-call transactions.
+    pub call_selector: Felt,
+    pub reason: String,
+}
+
+/// This event will be triggered every time a failed transaction is sent again, see
+/// [`TxRetryConfig`].
+#[derive(Event, Debug, Clone)]
+pub struct TxRetried {
+    pub id: TxId,
+    /// 1 for the first retry.
+    pub attempt: u32,
+    /// Why the previous attempt failed.
+    pub reason: String,
+}
+
+/// A transaction known to the [`TransactionTracker`].
+#[derive(Debug, Clone)]
+pub struct TrackedTx {
+    pub calls: Vec<Call>,
+    pub status: TxStatus,
+    pub tx_hash: Option<Felt>,
+    /// Elapsed app time at submission.
+    pub submitted_at: Duration,
+    /// Elapsed app time when the tracker first saw the final status, see
+    /// [`TransactionTracker::retention`].
+    pub finished_at: Option<Duration>,
+}
+
+/// Messages sent from the async submission tasks back to Bevy.
+enum TxUpdate {
+    Sent {
+        id: TxId,
+        tx_hash: Felt,
+    },
+    Retried {
+        id: TxId,
+        attempt: u32,
+        reason: String,
+    },
+    Finished {
+        id: TxId,
+        status: TxStatus,
+    },
+}
+
+/// Lets a [`TxBackend`] report the progress of one transaction, from any thread.
+#[derive(Clone)]
+pub struct TxReporter {
+    id: TxId,
+    sender: Sender<TxUpdate>,
+}
+
+impl TxReporter {
+    pub fn id(&self) -> TxId {
+        self.id
+    }
+
+    /// The transaction was accepted by the sequencer.
+    pub fn sent(&self, tx_hash: Felt) {
+        let _ = self.sender.send(TxUpdate::Sent {
+            id: self.id,
+            tx_hash,
+        });
+    }
+
+    /// The transaction failed and is about to be sent again.
+    pub fn retried(&self, attempt: u32, reason: String) {
+        let _ = self.sender.send(TxUpdate::Retried {
+            id: self.id,
+            attempt,
+            reason,
+        });
+    }
+
+    /// The transaction reached a final status.
+    pub fn finish(&self, status: TxStatus) {
+        let _ = self.sender.send(TxUpdate::Finished {
+            id: self.id,
+            status,
+        });
+    }
+}
+
+/// How the fee of a transaction is chosen.
+#[derive(Debug, Clone)]
+pub struct FeeSettings {
+    /// Transactions whose scaled estimate is above this are not sent. The others are sent with
+    /// resource bounds adding up to the scaled estimate, so they can't be charged more.
+    pub max_fee: Option<Felt>,
+    /// Margin applied to the estimated gas, which bounds the fee compared with `max_fee`.
+    pub estimation_multiplier: f64,
+}
+
+impl Default for FeeSettings {
+    fn default() -> Self {
+        Self {
+            max_fee: None,
+            estimation_multiplier: DEFAULT_FEE_ESTIMATION_MULTIPLIER,
+        }
+    }
+}
+
+/// Which failed transactions are sent again, with a fresh nonce.
+///
+/// Rapid submissions make Katana reject transactions whose nonce was already used by a
+/// transaction not included yet. Only [`TxStatus::Failed`] transactions are retried, never
+/// reverted ones: the contract would revert them again.
+#[derive(Debug, Clone)]
+pub struct TxRetryConfig {
+    /// Retries after the first attempt, 0 disabling them.
+    pub max_retries: u32,
+    /// A failure is retried when its reason contains any of these, ignoring case.
+    pub retry_on: Vec<String>,
+    /// Delay before refreshing the nonce, to let the previous transaction land.
+    pub delay: Duration,
+}
+
+impl Default for TxRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            retry_on: vec!["nonce".to_string()],
+            delay: Duration::from_millis(250),
+        }
+    }
+}
+
+impl TxRetryConfig {
+    pub fn should_retry(&self, reason: &str) -> bool {
+        let reason = reason.to_lowercase();
+        self.retry_on
+            .iter()
+            .any(|pattern| reason.contains(&pattern.to_lowercase()))
+    }
+}
+
+/// Logs the calls of every submitted transaction instead of sending them, to check what the
+/// inputs would do without touching the chain.
+///
+/// The transactions are reported as confirmed right away, so the game logic carries on. Counted
+/// by [`TransactionTracker::dry_runs`].
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct DryRun(pub bool);
+
+/// Everything a [`TxBackend`] may need to send a transaction.
+pub struct TxContext<'a> {
+    pub dojo: &'a DojoResource,
+    pub tokio: &'a TokioRuntime,
+    pub reporter: TxReporter,
+    /// How long the tracker waits before marking the transaction as timed out.
+    pub timeout: Duration,
+    pub fees: &'a FeeSettings,
+    pub retry: &'a TxRetryConfig,
+    tasks: &'a mut Vec<TaskHandle>,
+}
+
+impl TxContext<'_> {
+    /// Spawns a task on the Tokio runtime, aborted by [`TransactionTracker::cancel_all`].
+    ///
+    /// The task keeps the `tx_submit` span of the transaction.
+    pub fn spawn(&mut self, future: impl TaskFuture) {
+        let handle = task::spawn(self.tokio, future.in_current_span());
+        self.tasks.push(handle);
+    }
+}
+
+/// Sends transactions on behalf of the [`TransactionTracker`].
+///
+/// Swapping the backend lets tests run the game logic without Katana.
+pub trait TxBackend: Send + Sync + 'static {
+    fn send(&self, context: TxContext, calls: Vec<Call>);
+}
+
+/// Sends transactions with the account connected through [`DojoResource`].
+pub struct AccountBackend {
+    /// Delay between two receipt requests.
+    pub poll_interval: Duration,
+}
+
+impl Default for AccountBackend {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl TxBackend for AccountBackend {
+    fn send(&self, mut context: TxContext, calls: Vec<Call>) {
+        let reporter = context.reporter.clone();
+        let Some(account) = context.dojo.sn.account.clone() else {
+            reporter.finish(TxStatus::Failed("account not connected".to_string()));
+            return;
+        };
+
+        let poll_interval = self.poll_interval;
+        let max_polls = (context.timeout.as_millis() / poll_interval.as_millis().max(1)) as u32;
+
+        let fees = context.fees.clone();
+        let retry = context.retry.clone();
+
+        context.spawn(async move {
+            // Left to the account on the first attempt.
+            let mut nonce = None;
+            let mut attempt = 0;
+            let tx_hash = loop {
+                let result = async {
+                    let mut execution = account.execute_v3(calls.clone());
+                    if let Some(nonce) = nonce {
+                        execution = execution.nonce(nonce);
+                    }
+
+                    // Reverts are usually caught while estimating the fee, before sending.
+                    let estimate = execution
+                        .estimate_fee()
+                        .await
+                        .map_err(|error| failure_status(&error))?;
+                    let overall_fee = Felt::from(estimate.overall_fee);
+                    let bounds = FeeBounds::from_estimate(&estimate, fees.estimation_multiplier);
+                    let fee_bound = bounds.max_fee();
+                    debug!(
+                        target: LOG_TARGET,
+                        overall_fee = ?overall_fee,
+                        fee_bound = ?fee_bound,
+                        "Estimated transaction fee"
+                    );
+                    if let Some(max_fee) = fees.max_fee.filter(|max_fee| fee_bound > *max_fee) {
+                        return Err(TxStatus::Failed(format!(
+                            "estimated fee {fee_bound:#x} exceeds max_fee {max_fee:#x}"
+                        )));
+                    }
+
+                    // Sent with the bounds checked above, rather than estimated again.
+                    bounds
+                        .apply(execution)
+                        .send()
+                        .await
+                        .map(|result| result.transaction_hash)
+                        .map_err(|error| failure_status(&error))
+                }
+                .await;
+
+                match result {
+                    Ok(tx_hash) => break tx_hash,
+                    Err(TxStatus::Failed(reason))
+                        if attempt < retry.max_retries && retry.should_retry(&reason) =>
+                    {
+                        attempt += 1;
+                        reporter.retried(attempt, reason);
+                        task::sleep(retry.delay).await;
+                        nonce = account.get_nonce().await.ok();
+                    }
+                    Err(status) => {
+                        reporter.finish(status);
+                        return;
+                    }
+                }
+            };
+            reporter.sent(tx_hash);
+
+            for _ in 0..max_polls {
+                match account.provider().get_transaction_receipt(tx_hash).await {
+                    Ok(receipt) => {
+                        let status = match receipt.receipt.execution_result() {
+                            ExecutionResult::Succeeded => TxStatus::Confirmed,
+                            ExecutionResult::Reverted { reason } => {
+                                TxStatus::Reverted(parse_revert_reason(reason))
+                            }
+                        };
+                        reporter.finish(status);
+                        return;
+                    }
+                    // Not included yet.
+                    Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => {
+                        task::sleep(poll_interval).await
+                    }
+                    Err(error) => {
+                        reporter.finish(TxStatus::Failed(error.to_string()));
+                        return;
+                    }
+                }
+            }
+            // Timing out is handled on the Bevy side.
+        });
+    }
+}
+
+/// Classifies an error returned while estimating or sending a transaction.
+fn failure_status(error: &(impl std::fmt::Display + std::fmt::Debug)) -> TxStatus {
+    revert_reason(&error.to_string())
+        .or_else(|| revert_reason(&format!("{error:?}")))
+        .map_or_else(|| TxStatus::Failed(error.to_string()), TxStatus::Reverted)
+}
+
+/// The resource bounds a transaction is sent with: the estimated gas amounts scaled by the
+/// estimation multiplier, at the estimated prices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FeeBounds {
+    l1_gas: u64,
+    l1_gas_price: u128,
+    l2_gas: u64,
+    l2_gas_price: u128,
+    l1_data_gas: u64,
+    l1_data_gas_price: u128,
+}
+
+impl FeeBounds {
+    fn from_estimate(estimate: &FeeEstimate, multiplier: f64) -> Self {
+        let scale = |gas: u64| (gas as f64 * multiplier) as u64;
+        Self {
+            l1_gas: scale(estimate.l1_gas_consumed),
+            l1_gas_price: estimate.l1_gas_price,
+            l2_gas: scale(estimate.l2_gas_consumed),
+            l2_gas_price: estimate.l2_gas_price,
+            l1_data_gas: scale(estimate.l1_data_gas_consumed),
+            l1_data_gas_price: estimate.l1_data_gas_price,
+        }
+    }
+
+    /// The most the transaction can be charged, saturating on overflow.
+    fn max_fee(&self) -> Felt {
+        let fee = [
+            (self.l1_gas, self.l1_gas_price),
+            (self.l2_gas, self.l2_gas_price),
+            (self.l1_data_gas, self.l1_data_gas_price),
+        ]
+        .iter()
+        .fold(0_u128, |fee, (gas, price)| {
+            fee.saturating_add(u128::from(*gas).saturating_mul(*price))
+        });
+        Felt::from(fee)
+    }
+
+    fn apply<'a, A>(&self, execution: ExecutionV3<'a, A>) -> ExecutionV3<'a, A> {
+        execution
+            .l1_gas(self.l1_gas)
+            .l1_gas_price(self.l1_gas_price)
+            .l2_gas(self.l2_gas)
+            .l2_gas_price(self.l2_gas_price)
+            .l1_data_gas(self.l1_data_gas)
+            .l1_data_gas_price(self.l1_data_gas_price)
+    }
+}
+
+/// Extracts the revert reason from an error, if it comes from the contract execution.
+fn revert_reason(raw: &str) -> Option<String> {
+    (raw.contains("Failure reason") || raw.contains("Execution failed"))
+        .then(|| parse_revert_reason(raw))
+}
+
+/// Extracts the innermost, human readable, reason of a revert.
+///
+/// Sequencers nest the errors of every called contract and end with
+/// `Failure reason: 0x706c... ('player not spawned').`, while Katana may only quote the string,
+/// as in `Failure reason: "player not spawned"`, or send the raw felt.
+pub fn parse_revert_reason(raw: &str) -> String {
+    const MARKER: &str = "Failure reason:";
+    let reason = match raw.rfind(MARKER) {
+        Some(index) => &raw[index + MARKER.len()..],
+        None => raw,
+    }
+    .trim();
+
+    if let Some(decoded) = between(reason, "('", "')") {
+        return decoded.to_string();
+    }
+    if let Some(quoted) = between(reason, "\"", "\"") {
+        return quoted.to_string();
+    }
+    if let Some(decoded) = reason
+        .split_whitespace()
+        .next()
+        .and_then(decode_short_string)
+    {
+        return decoded;
+    }
+
+    reason
+        .lines()
+        .rfind(|line| !line.trim().is_empty())
+        .unwrap_or(reason)
+        .trim()
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn between<'a>(value: &'a str, open: &str, close: &str) -> Option<&'a str> {
+    let start = value.find(open)? + open.len();
+    let end = value[start..].find(close)? + start;
+    Some(&value[start..end])
+}
+
+/// Decodes a Cairo short string, e.g. `0x6e6f7420737061776e6564` into `not spawned`.
+fn decode_short_string(hex: &str) -> Option<String> {
+    let hex = hex.trim_end_matches(|c: char| !c.is_ascii_hexdigit());
+    if !hex.starts_with("0x") {
+        return None;
+    }
+    let bytes = Felt::from_hex(hex).ok()?.to_bytes_be();
+    let text: String = bytes
+        .iter()
+        .skip_while(|byte| **byte == 0)
+        .map(|byte| char::from(*byte))
+        .collect();
+    (!text.is_empty() && text.chars().all(|c| c.is_ascii_graphic() || c == ' ')).then_some(text)
+}
+
+/// Tracks every transaction submitted through [`TransactionTracker::submit`].
+#[derive(Resource)]
+pub struct TransactionTracker {
+    /// Marks pending transactions as [`TxStatus::TimedOut`] after this long.
+    pub timeout: Duration,
+    /// Forgets the transactions this long after they reached a final status, so their status
+    /// is `None` from then on.
+    pub retention: Duration,
+    /// Kept in sync with the [`DojoConfig`](crate::constants::dojo::DojoConfig).
+    pub fees: FeeSettings,
+    pub retry: TxRetryConfig,
+    backend: Box<dyn TxBackend>,
+    transactions: HashMap<TxId, TrackedTx>,
+    next_id: u64,
+    sender: Sender<TxUpdate>,
+    receiver: Mutex<Receiver<TxUpdate>>,
+    /// Tasks spawned by the backend, so they can be aborted on disconnect.
+    tasks: Vec<TaskHandle>,
+    /// Set while the account reconnects, see [`TransactionTracker::hold`].
+    holding: bool,
+    /// Transactions submitted while holding, oldest first.
+    queued: Vec<(TxId, Vec<Call>)>,
+    /// The ids in `queued`, not to scan it for every transaction.
+    queued_ids: HashSet<TxId>,
+    /// Kept in sync with the [`DryRun`] resource.
+    dry_run: bool,
+    dry_runs: u64,
+    /// The configured entrypoints, to name the logged selectors.
+    known_selectors: Vec<(Felt, &'static str)>,
+}
+
+impl Default for TransactionTracker {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            timeout: Duration::from_secs(30),
+            retention: Duration::from_secs(60),
+            fees: FeeSettings::default(),
+            retry: TxRetryConfig::default(),
+            backend: Box::new(AccountBackend::default()),
+            transactions: HashMap::default(),
+            next_id: 0,
+            sender,
+            receiver: Mutex::new(receiver),
+            tasks: Vec::new(),
+            holding: false,
+            queued: Vec::new(),
+            queued_ids: HashSet::default(),
+            dry_run: false,
+            dry_runs: 0,
+            known_selectors: Vec::new(),
+        }
+    }
+}
+
+impl TransactionTracker {
+    /// Submits the calls as a single transaction through the backend.
+    ///
+    /// `now` is the elapsed app time, used for the timeout. While [holding](Self::hold), the
+    /// transaction stays pending until it's released.
+    pub fn submit(
+        &mut self,
+        dojo: &DojoResource,
+        tokio: &TokioRuntime,
+        calls: Vec<Call>,
+        now: Duration,
+    ) -> TxId {
+        let id = TxId(self.next_id);
+        self.next_id += 1;
+
+        self.transactions.insert(
+            id,
+            TrackedTx {
+                calls: calls.clone(),
+                status: TxStatus::Pending,
+                tx_hash: None,
+                submitted_at: now,
+                finished_at: None,
+            },
+        );
+
+        if self.holding {
+            debug!(target: LOG_TARGET, id = id.0, "Queuing transaction until the account is back");
+            self.queued.push((id, calls));
+            self.queued_ids.insert(id);
+        } else {
+            self.dispatch(id, dojo, tokio, calls);
+        }
+        id
+    }
+
+    fn dispatch(&mut self, id: TxId, dojo: &DojoResource, tokio: &TokioRuntime, calls: Vec<Call>) {
+        let _span = info_span!(
+            target: LOG_TARGET,
+            "tx_submit",
+            id = id.0,
+            calls = calls.len(),
+            selector = ?calls.first().map(|call| call.selector)
+        )
+        .entered();
+
+        if self.dry_run {
+            self.dry_runs += 1;
+            for call in &calls {
+                info!(
+                    target: LOG_TARGET,
+                    to = ?call.to,
+                    selector = %self.selector_name(call.selector),
+                    calldata = ?call.calldata,
+                    "Dry run, not submitting call"
+                );
+            }
+            let _ = self.sender.send(TxUpdate::Finished {
+                id,
+                status: TxStatus::Confirmed,
+            });
+            return;
+        }
+        debug!(target: LOG_TARGET, "Submitting transaction");
+
+        // Forget the tasks that already completed.
+        self.tasks.retain(|task| !task.is_finished());
+        let context = TxContext {
+            dojo,
+            tokio,
+            reporter: TxReporter {
+                id,
+                sender: self.sender.clone(),
+            },
+            timeout: self.timeout,
+            fees: &self.fees,
+            retry: &self.retry,
+            tasks: &mut self.tasks,
+        };
+        self.backend.send(context, calls);
+    }
+
+    /// Queues the transactions submitted from now on instead of sending them, e.g. while the
+    /// account reconnects. They don't time out while queued.
+    pub fn hold(&mut self) {
+        self.holding = true;
+    }
+
+    pub fn is_holding(&self) -> bool {
+        self.holding
+    }
+
+    /// Sends the queued transactions, their timeout starting `now`, and stops queuing.
+    pub fn release(&mut self, dojo: &DojoResource, tokio: &TokioRuntime, now: Duration) {
+        self.holding = false;
+        self.queued_ids.clear();
+        let queued = std::mem::take(&mut self.queued);
+        if !queued.is_empty() {
+            info!(
+                target: LOG_TARGET,
+                transactions = queued.len(),
+                "Sending the queued transactions"
+            );
+        }
+        for (id, calls) in queued {
+            let Some(tracked) = self.transactions.get_mut(&id) else {
+                continue;
+            };
+            tracked.submitted_at = now;
+            self.dispatch(id, dojo, tokio, calls);
+        }
+    }
+
+    /// Fails the queued transactions with `reason`, and stops queuing.
+    pub fn fail_queued(&mut self, reason: &str) {
+        self.holding = false;
+        self.queued_ids.clear();
+        for (id, _) in self.queued.drain(..) {
+            let _ = self.sender.send(TxUpdate::Finished {
+                id,
+                status: TxStatus::Failed(reason.to_string()),
+            });
+        }
+    }
+
+    /// Transactions logged instead of sent, see [`DryRun`].
+    pub fn dry_runs(&self) -> u64 {
+        self.dry_runs
+    }
+
+    /// Whether the transactions are logged instead of sent, see [`DryRun`].
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// The entrypoint name of a configured selector, or the selector in hex.
+    fn selector_name(&self, selector: Felt) -> String {
+        self.known_selectors
+            .iter()
+            .find(|(known, _)| *known == selector)
+            .map_or_else(|| format!("{selector:#x}"), |(_, name)| name.to_string())
+    }
+
+    /// Replaces the way transactions are sent, e.g. with a test double.
+    pub fn set_backend(&mut self, backend: impl TxBackend) {
+        self.backend = Box::new(backend);
+    }
+
+    pub fn get(&self, id: TxId) -> Option<&TrackedTx> {
+        self.transactions.get(&id)
+    }
+
+    pub fn status(&self, id: TxId) -> Option<&TxStatus> {
+        self.get(id).map(|tracked| &tracked.status)
+    }
+
+    /// Transactions still waiting for a receipt.
+    pub fn pending(&self) -> impl Iterator<Item = (TxId, &TrackedTx)> {
+        self.transactions
+            .iter()
+            .filter(|(_, tracked)| !tracked.status.is_final())
+            .map(|(id, tracked)| (*id, tracked))
+    }
+
+    /// Aborts the in-flight submission tasks and fails every pending transaction.
+    ///
+    /// Returns the transactions that were still pending.
+    pub fn cancel_all(&mut self) -> Vec<TxId> {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        self.holding = false;
+        self.queued.clear();
+        self.queued_ids.clear();
+
+        let mut cancelled = Vec::new();
+        for (id, tracked) in self.transactions.iter_mut() {
+            if !tracked.status.is_final() {
+                tracked.status = TxStatus::Failed("cancelled by disconnect".to_string());
+                cancelled.push(*id);
+            }
+        }
+        cancelled
+    }
+
+    /// Forgets the transactions that reached a final status.
+    pub fn clear_finished(&mut self) {
+        self.transactions
+            .retain(|_, tracked| !tracked.status.is_final());
+    }
+
+    /// Forgets the transactions finished for longer than the
+    /// [retention](Self::retention), returning how many.
+    pub fn prune_finished(&mut self, now: Duration) -> usize {
+        let before = self.transactions.len();
+        let retention = self.retention;
+        self.transactions.retain(|_, tracked| {
+            if !tracked.status.is_final() {
+                return true;
+            }
+            let finished_at = *tracked.finished_at.get_or_insert(now);
+            now.saturating_sub(finished_at) < retention
+        });
+        before - self.transactions.len()
+    }
+}
+
+/// Applies the updates sent by the submission tasks and times out stale transactions.
+fn poll_transactions(
+    time: Res<Time>,
+    mut tracker: ResMut<TransactionTracker>,
+    mut ev_status_changed: EventWriter<TxStatusChanged>,
+    mut ev_reverted: EventWriter<TxReverted>,
+    mut ev_retried: EventWriter<TxRetried>,
+    mut ev_error: EventWriter<DojoErrorEvent>,
+) {
+    let updates: Vec<TxUpdate> = tracker
+        .receiver
+        .lock()
+        .map(|receiver| receiver.try_iter().collect())
+        .unwrap_or_default();
+
+    for update in updates {
+        match update {
+            TxUpdate::Sent { id, tx_hash } => {
+                if let Some(tracked) = tracker.transactions.get_mut(&id) {
+                    debug!(target: LOG_TARGET, id = id.0, tx_hash = ?tx_hash, "Transaction sent");
+                    tracked.tx_hash = Some(tx_hash);
+                }
+            }
+            TxUpdate::Retried {
+                id,
+                attempt,
+                reason,
+            } => {
+                info!(
+                    target: LOG_TARGET,
+                    id = id.0,
+                    attempt,
+                    "Retrying transaction after: {}",
+                    reason
+                );
+                ev_retried.write(TxRetried {
+                    id,
+                    attempt,
+                    reason,
+                });
+            }
+            TxUpdate::Finished { id, status } => {
+                let Some(tracked) = tracker.transactions.get_mut(&id) else {
+                    continue;
+                };
+                // A late receipt doesn't override a timeout.
+                if tracked.status.is_final() {
+                    continue;
+                }
+                match &status {
+                    TxStatus::Failed(reason) => {
+                        warn!(target: LOG_TARGET, id = id.0, "Transaction failed: {}", reason);
+                        ev_error.write(DojoError::TxSubmit(reason.clone()).into());
+                    }
+                    TxStatus::Reverted(reason) => {
+                        warn!(target: LOG_TARGET, id = id.0, "Transaction reverted: {}", reason);
+                        ev_reverted.write(TxReverted {
+                            id,
+                            call_selector: tracked
+                                .calls
+                                .first()
+                                .map_or(Felt::ZERO, |call| call.selector),
+                            reason: reason.clone(),
+                        });
+                    }
+                    _ => debug!(target: LOG_TARGET, id = id.0, ?status, "Transaction finished"),
+                }
+                tracked.status = status.clone();
+                ev_status_changed.write(TxStatusChanged { id, status });
+            }
+        }
+    }
+
+    let now = time.elapsed();
+    let timeout = tracker.timeout;
+    let tracker = &mut *tracker;
+    for (id, tracked) in tracker.transactions.iter_mut() {
+        let queued = tracker.queued_ids.contains(id);
+        if !tracked.status.is_final()
+            && !queued
+            && now.saturating_sub(tracked.submitted_at) > timeout
+        {
+            warn!(
+                target: LOG_TARGET,
+                id = id.0,
+                tx_hash = ?tracked.tx_hash,
+                "Transaction timed out"
+            );
+            ev_error.write(DojoError::TxSubmit(format!("transaction {} timed out", id.0)).into());
+            tracked.status = TxStatus::TimedOut;
+            ev_status_changed.write(TxStatusChanged {
+                id: *id,
+                status: TxStatus::TimedOut,
+            });
+        }
+    }
+}
+
+/// Keeps the tracker from growing with every transaction of the session.
+fn prune_transactions(time: Res<Time>, mut tracker: ResMut<TransactionTracker>) {
+    let pruned = tracker.prune_finished(time.elapsed());
+    if pruned > 0 {
+        debug!(target: LOG_TARGET, pruned, "Forgot the finished transactions");
+    }
+}
+
+fn sync_dry_run(
+    dry_run: Res<DryRun>,
+    dojo_state: Res<DojoSystemState>,
+    mut tracker: ResMut<TransactionTracker>,
+) {
+    if tracker.dry_run != dry_run.0 {
+        info!(target: LOG_TARGET, enabled = dry_run.0, "Transaction dry run");
+        tracker.dry_run = dry_run.0;
+    }
+    let known_selectors = vec![
+        (dojo_state.config.spawn_selector, "spawn"),
+        (dojo_state.config.move_selector, "move"),
+    ];
+    // Avoids triggering change detection every time the state changes.
+    if tracker.known_selectors != known_selectors {
+        tracker.known_selectors = known_selectors;
+    }
+}
+
+fn sync_fee_settings(dojo_state: Res<DojoSystemState>, mut tracker: ResMut<TransactionTracker>) {
+    let fees = FeeSettings {
+        max_fee: dojo_state.config.max_fee,
+        estimation_multiplier: dojo_state.config.fee_estimation_multiplier,
+    };
+    // Avoids triggering change detection every time the state changes.
+    if tracker.fees.max_fee != fees.max_fee
+        || tracker.fees.estimation_multiplier != fees.estimation_multiplier
+    {
+        tracker.fees = fees;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+    use starknet::core::types::PriceUnit;
+
+    use super::*;
+    use crate::demo::dojo::mock::MockDojo;
+
+    #[test]
+    fn fee_bounds_add_up_to_the_scaled_estimate() {
+        let estimate = FeeEstimate {
+            l1_gas_consumed: 0,
+            l1_gas_price: 10,
+            l2_gas_consumed: 1_000,
+            l2_gas_price: 3,
+            l1_data_gas_consumed: 100,
+            l1_data_gas_price: 2,
+            overall_fee: 3_200,
+            unit: PriceUnit::Fri,
+        };
+        let bounds = FeeBounds::from_estimate(&estimate, 1.5);
+        assert_eq!(bounds.l2_gas, 1_500);
+        assert_eq!(bounds.l1_data_gas, 150);
+        assert_eq!(bounds.max_fee(), Felt::from(4_800_u64));
+    }
+
+    #[test]
+    fn finished_transactions_are_pruned_after_the_retention() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            // Virtual time advances by at most 250ms per frame.
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                100,
+            )))
+            .init_resource::<DojoSystemState>()
+            .add_event::<DojoErrorEvent>()
+            .add_plugins(plugin);
+        let mut tracker = app.world_mut().resource_mut::<TransactionTracker>();
+        tracker.retention = Duration::from_millis(500);
+        let mut mock = MockDojo::default();
+        mock.outcome = Some(TxStatus::Confirmed);
+        tracker.set_backend(mock);
+        let id = tracker.submit(
+            &DojoResource::default(),
+            &TokioRuntime::default(),
+            vec![],
+            Duration::ZERO,
+        );
+
+        app.update();
+        let tracker = app.world().resource::<TransactionTracker>();
+        assert_eq!(tracker.status(id), Some(&TxStatus::Confirmed));
+
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(
+            app.world().resource::<TransactionTracker>().status(id),
+            None
+        );
+    }
+
+    #[test]
+    fn katana_revert_reasons_are_decoded() {
+        let raw = "Transaction execution has failed:\n\
+            0: Error in the called contract (contract address: 0x049f, class hash: 0x02a1, \
+            selector: 0x0239):\n\
+            Execution failed. Failure reason:\n\
+            (0x706c61796572206e6f7420737061776e6564 ('player not spawned'), \
+            0x454e545259504f494e545f4641494c4544 ('ENTRYPOINT_FAILED')).";
+        assert_eq!(revert_reason(raw).as_deref(), Some("player not spawned"));
+
+        // Older versions only send the felt.
+        assert_eq!(
+            parse_revert_reason("Execution failed. Failure reason: 0x6e6f7420737061776e6564."),
+            "not spawned"
+        );
+    }
+
+    #[test]
+    fn sequencer_revert_reasons_are_decoded() {
+        let raw = "Error in the called contract (contract address: 0x03f5, class hash: 0x0741, \
+            selector: 0x015d):\n\
+            Error at pc=0:4835:\n\
+            Cairo traceback (most recent call last):\n\
+            Unknown location (pc=0:67)\n\n\
+            Error in the called contract (contract address: 0x049f, class hash: 0x02a1, \
+            selector: 0x0239):\n\
+            Execution failed. Failure reason: 0x4e6f206d6f766573206c656674 ('No moves left').";
+        assert_eq!(revert_reason(raw).as_deref(), Some("No moves left"));
+    }
+
+    #[test]
+    fn unknown_reasons_are_kept_raw() {
+        assert_eq!(revert_reason("Invalid transaction nonce"), None);
+        assert_eq!(
+            parse_revert_reason("Account validation failed:\nInvalid signature."),
+            "Invalid signature"
+        );
+        assert_eq!(
+            parse_revert_reason("Failure reason: Out of gas."),
+            "Out of gas"
+        );
+        assert_eq!(decode_short_string("0x0"), None);
+        assert_eq!(decode_short_string("0x0102"), None);
+        assert_eq!(decode_short_string("spawn"), None);
+    }
+}
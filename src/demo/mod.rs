@@ -6,7 +6,7 @@
 use bevy::prelude::*;
 
 mod animation;
-mod dojo;
+pub mod dojo;
 pub mod level;
 mod movement;
 pub mod player;
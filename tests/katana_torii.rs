@@ -0,0 +1,117 @@
+//! End-to-end test against the world of `contracts/`, booted with its `compose.yaml`: Katana,
+//! the `sozo migrate`, then Torii.
+//!
+//! Needs Docker, and the `it-containers` feature:
+//!
+//! ```sh
+//! cargo test --features it-containers --test katana_torii
+//! ```
+
+#![cfg(feature = "it-containers")]
+
+use bevy::prelude::*;
+use bevy_dojo_starter::{
+    constants::dojo::DojoConfig,
+    demo::dojo::{
+        ConnectNow, DojoSystemState, actions::PlayerAction, intro::PositionUpdatedEvent, mock,
+    },
+};
+use std::{
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+use testcontainers::{
+    compose::DockerCompose,
+    core::{IntoContainerPort, WaitFor, wait::HttpWaitStrategy},
+};
+
+/// Longest wait for each step of the test.
+const STEP_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// `Direction::Right` in `contracts/src/models.cairo`.
+const RIGHT: u8 = 1;
+
+/// Runs frames until `done` returns a value, panicking after [`STEP_TIMEOUT`].
+fn update_until<T>(app: &mut App, step: &str, mut done: impl FnMut(&mut App) -> Option<T>) -> T {
+    let deadline = Instant::now() + STEP_TIMEOUT;
+    loop {
+        app.update();
+        if let Some(value) = done(app) {
+            return value;
+        }
+        assert!(Instant::now() < deadline, "timed out waiting for {step}");
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn spawn_and_move_come_back_from_torii() {
+    let compose_file = Path::new(env!("CARGO_MANIFEST_DIR")).join("contracts/compose.yaml");
+    // Torii only starts once the migration succeeded.
+    let mut compose = DockerCompose::with_local_client(&[compose_file])
+        .with_build(true)
+        .with_wait(false)
+        .with_wait_for_service(
+            "torii",
+            WaitFor::http(
+                HttpWaitStrategy::new("/graphql")
+                    .with_port(8080.tcp())
+                    .with_expected_status_code(200_u16),
+            ),
+        );
+    // The app runs its own runtime, which can't be started from within another one.
+    let runtime = tokio::runtime::Runtime::new().expect("failed to start a Tokio runtime");
+    runtime
+        .block_on(compose.up())
+        .expect("failed to start contracts/compose.yaml");
+
+    // The ports published by `compose.yaml`, and the world of its `manifest_dev.json`, which
+    // the default configuration already points to.
+    let mut app = mock::headless_app_with_config(DojoConfig {
+        torii_url: "http://localhost:8080".to_string(),
+        katana_url: "http://localhost:5050".to_string(),
+        namespace: "dojo_starter".to_string(),
+        ..DojoConfig::default()
+    });
+    app.world_mut().send_event(ConnectNow);
+    update_until(&mut app, "the Dojo connections", |app| {
+        app.world()
+            .resource::<DojoSystemState>()
+            .is_ready()
+            .then_some(())
+    });
+    let player = app
+        .world()
+        .resource::<DojoSystemState>()
+        .local_player_address
+        .expect("the dev account is connected");
+
+    let mut positions = app
+        .world()
+        .resource::<Events<PositionUpdatedEvent>>()
+        .get_cursor();
+    let mut local_position = |app: &mut App| {
+        let events = app.world().resource::<Events<PositionUpdatedEvent>>();
+        positions
+            .read(events)
+            .filter(|ev| ev.position.player == player)
+            .map(|ev| (ev.position.x, ev.position.y))
+            .last()
+    };
+
+    // `spawn` puts the player at (10, 10) of a new world.
+    app.world_mut().send_event(PlayerAction::Spawn);
+    let spawned = update_until(&mut app, "the spawned position", &mut local_position);
+    assert_eq!(spawned, (10, 10));
+
+    app.world_mut().send_event(PlayerAction::Move(RIGHT));
+    let moved = update_until(&mut app, "the moved position", |app| {
+        local_position(app).filter(|position| *position != spawned)
+    });
+    assert_eq!(moved, (11, 10));
+
+    runtime
+        .block_on(compose.down())
+        .expect("failed to stop contracts/compose.yaml");
+}